@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use Shape;
+use UvRect;
+
+/// List of frames, where each frame is a region of a larger texture.
+///
+/// When an atlas is passed to `Ui::set_atlas`, the `Shape::Image` whose name is a frame of the
+/// atlas are returned by `draw` with the name of the texture and the region of the frame. This
+/// allows renderers to draw all the frames of a texture in a single batch.
+#[derive(Clone, Debug, Default)]
+pub struct Atlas {
+    frames: HashMap<String, (String, UvRect)>,
+}
+
+impl Atlas {
+    /// Builds an empty atlas.
+    #[inline]
+    pub fn new() -> Atlas {
+        Atlas {
+            frames: HashMap::new(),
+        }
+    }
+
+    /// Adds a frame to the atlas. Replaces the existing frame with the same name, if any.
+    #[inline]
+    pub fn add_frame<S1, S2>(&mut self, name: S1, texture: S2, region: UvRect)
+                             where S1: Into<String>, S2: Into<String>
+    {
+        self.frames.insert(name.into(), (texture.into(), region));
+    }
+
+    /// Returns the name of the texture and the region of a frame.
+    #[inline]
+    pub fn frame(&self, name: &str) -> Option<(&str, &UvRect)> {
+        self.frames.get(name).map(|&(ref texture, ref region)| (&texture[..], region))
+    }
+
+    /// If the shape is an image whose name is a frame of the atlas, replaces its name with the
+    /// name of the texture and its region with the corresponding region of the texture.
+    pub fn resolve(&self, shape: Shape) -> Shape {
+        match shape {
+            Shape::Image { matrix, name, uv, tint } => {
+                match self.frames.get(&name) {
+                    Some(&(ref texture, ref region)) => {
+                        Shape::Image { matrix: matrix, name: texture.clone(),
+                                       uv: region.sub_region(&uv), tint: tint }
+                    },
+                    None => Shape::Image { matrix: matrix, name: name, uv: uv, tint: tint },
+                }
+            },
+            shape => shape,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Gives access to the clipboard of the system. Passed to the `Ui` with `set_clipboard`.
+///
+/// The `Ui` reads the clipboard when the user pastes text, and writes it when a widget returns
+/// some text in the `clipboard` field of its `EventOutcome`.
+pub trait Clipboard: Send + Sync {
+    /// Returns the text contained in the clipboard, or `None` if it doesn't contain any text.
+    fn get_text(&self) -> Option<String>;
+
+    /// Replaces the content of the clipboard.
+    fn set_text(&self, text: String);
+}
+
+impl<C> Clipboard for Arc<C> where C: Clipboard {
+    #[inline]
+    fn get_text(&self) -> Option<String> {
+        (**self).get_text()
+    }
+
+    #[inline]
+    fn set_text(&self, text: String) {
+        (**self).set_text(text)
+    }
+}
+
+/// Clipboard that keeps its content in memory and isn't shared with the rest of the system.
+///
+/// This is the clipboard used by default by the `Ui`.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    content: Mutex<Option<String>>,
+}
+
+impl MemoryClipboard {
+    /// Builds an empty clipboard.
+    #[inline]
+    pub fn new() -> MemoryClipboard {
+        MemoryClipboard {
+            content: Mutex::new(None),
+        }
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    #[inline]
+    fn get_text(&self) -> Option<String> {
+        self.content.lock().unwrap().clone()
+    }
+
+    #[inline]
+    fn set_text(&self, text: String) {
+        *self.content.lock().unwrap() = Some(text);
+    }
+}
@@ -54,12 +54,24 @@ use std::any::Any;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+pub use atlas::Atlas;
+pub use clipboard::Clipboard;
+pub use clipboard::MemoryClipboard;
 pub use matrix::Matrix;
 pub use shape::Shape;
+pub use shape::UvRect;
+pub use text::FontMetrics;
+pub use text::MonospaceFontMetrics;
+pub use text::TextStyle;
+pub use ui::ClipRect;
+pub use ui::DrawList;
 pub use ui::Ui;
 
 pub mod predefined;
+pub mod text;
 
+mod atlas;
+mod clipboard;
 mod matrix;
 mod shape;
 mod ui;
@@ -73,6 +85,28 @@ pub struct EventOutcome {
     /// Events to propagate to the parent (in addition to the current event if
     /// `propagate_to_parent` is true).
     pub events_for_parent: Vec<Box<Any>>,
+    /// If `true`, the element will receive the focus. Ignored if the element isn't focusable.
+    /// Default is `false`.
+    pub request_focus: bool,
+    /// If `true`, the element captures the pointer: it receives the `MouseMoveEvent`s and
+    /// `MouseUpEvent`s even if the cursor isn't over it, until all the mouse buttons are
+    /// released. Ignored if no mouse button is pressed. Default is `false`.
+    pub capture_pointer: bool,
+    /// If `Some`, starts a drag and drop. Only taken into account when handling a
+    /// `predefined::DragStartEvent`. Default is `None`.
+    pub start_drag: Option<Drag>,
+    /// If `Some`, the text is put in the clipboard of the `Ui`. Default is `None`.
+    pub clipboard: Option<String>,
+}
+
+/// Describes a drag and drop started by a widget.
+pub struct Drag {
+    /// Data that is sent to the widget on which the drag and drop ends, as part of the
+    /// `predefined::DropEvent`.
+    pub payload: Box<Any + Send>,
+    /// Shapes drawn on top of everything else during the drag and drop. Their matrices are in
+    /// viewport coordinates, relative to the position of the cursor.
+    pub ghost: Vec<Shape>,
 }
 
 impl Default for EventOutcome {
@@ -81,6 +115,10 @@ impl Default for EventOutcome {
             refresh_layout: false,
             propagate_to_parent: true,
             events_for_parent: Vec::new(),
+            request_focus: false,
+            capture_pointer: false,
+            start_drag: None,
+            clipboard: None,
         }
     }
 }
@@ -93,6 +131,18 @@ pub trait Widget: Send + Sync + 'static {
     /// The `alignment` is just an indication passed by the parent.
     fn build_layout(&self, height_per_width: f32, alignment: Alignment) -> Layout;
 
+    /// Same as `build_layout`, but with the metrics of the font used to draw text. This is the
+    /// method that the `Ui` calls. Widgets that draw text should override it in order to
+    /// measure their text.
+    ///
+    /// The default implementation calls `build_layout`.
+    #[inline]
+    fn build_layout_with_metrics(&self, height_per_width: f32, alignment: Alignment,
+                                 _metrics: &FontMetrics) -> Layout
+    {
+        self.build_layout(height_per_width, alignment)
+    }
+
     /// This method is called before drawing. It should return `true` if the layout of this element
     /// should be rebuilt.
     ///
@@ -105,6 +155,35 @@ pub trait Widget: Send + Sync + 'static {
         false
     }
 
+    /// Returns `true` if the widget can receive the focus. Keyboard events are sent to the
+    /// focused widget.
+    ///
+    /// The default implementation returns `false`.
+    #[inline]
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    /// Returns how the shape of the given index in the list returned by `build_layout` reacts
+    /// to the mouse cursor.
+    ///
+    /// The default implementation returns `HitTestMode::Normal`.
+    #[inline]
+    fn hit_test_mode(&self, _shape: usize) -> HitTestMode {
+        HitTestMode::Normal
+    }
+
+    /// Returns the rectangle of the text caret of the widget, if it has one, as a matrix that
+    /// transforms the square from `-1.0` to `1.0` into the widget's coordinates.
+    ///
+    /// This is used by `Ui::caret_rect` to place the candidate window of input methods.
+    ///
+    /// The default implementation returns `None`.
+    #[inline]
+    fn caret_rect(&self) -> Option<Matrix> {
+        None
+    }
+
     /// The widget received an event. It can update itself, then it should return an `EventOutcome`
     /// indicating the library what to do next. The default implementation returns
     /// `Default::default()`.
@@ -113,7 +192,29 @@ pub trait Widget: Send + Sync + 'static {
     ///
     /// * `predefined::MouseEnterEvent`
     /// * `predefined::MouseLeaveEvent`
+    /// * `predefined::MouseMoveEvent`
+    /// * `predefined::MouseDownEvent`
+    /// * `predefined::MouseUpEvent`
     /// * `predefined::MouseClick`
+    /// * `predefined::MouseDoubleClick`
+    /// * `predefined::ScrollEvent`
+    /// * `predefined::DragStartEvent`
+    /// * `predefined::DragMoveEvent`
+    /// * `predefined::DropEvent`
+    /// * `predefined::DragEndEvent`
+    /// * `predefined::TouchStartEvent`
+    /// * `predefined::TouchMoveEvent`
+    /// * `predefined::TouchEndEvent`
+    /// * `predefined::KeyDownEvent`
+    /// * `predefined::KeyUpEvent`
+    /// * `predefined::CharacterEvent`
+    /// * `predefined::ImePreeditEvent`
+    /// * `predefined::ImeCommitEvent`
+    /// * `predefined::CopyEvent`
+    /// * `predefined::CutEvent`
+    /// * `predefined::PasteEvent`
+    /// * `predefined::FocusGainedEvent`
+    /// * `predefined::FocusLostEvent`
     /// * Any other event produced by another widget.
     ///
     #[inline]
@@ -128,11 +229,33 @@ impl<T> Widget for Mutex<T> where T: Widget {
         self.lock().unwrap().build_layout(height_per_width, alignment)
     }
 
+    #[inline]
+    fn build_layout_with_metrics(&self, height_per_width: f32, alignment: Alignment,
+                                 metrics: &FontMetrics) -> Layout
+    {
+        self.lock().unwrap().build_layout_with_metrics(height_per_width, alignment, metrics)
+    }
+
     #[inline]
     fn needs_rebuild(&self) -> bool {
         self.lock().unwrap().needs_rebuild()
     }
 
+    #[inline]
+    fn is_focusable(&self) -> bool {
+        self.lock().unwrap().is_focusable()
+    }
+
+    #[inline]
+    fn hit_test_mode(&self, shape: usize) -> HitTestMode {
+        self.lock().unwrap().hit_test_mode(shape)
+    }
+
+    #[inline]
+    fn caret_rect(&self) -> Option<Matrix> {
+        self.lock().unwrap().caret_rect()
+    }
+
     #[inline]
     fn handle_event(&self, event: &Any, child: Option<usize>) -> EventOutcome {
         self.lock().unwrap().handle_event(event, child)
@@ -171,6 +294,63 @@ impl Default for VerticalAlignment {
     }
 }
 
+/// How a shape reacts to the mouse cursor.
+///
+/// Only the topmost shape under the cursor is taken into account, in other words the last one
+/// in the list returned by `Ui::draw`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HitTestMode {
+    /// The widget that owns the shape receives the mouse events, and the shapes below it don't.
+    #[default]
+    Normal,
+    /// The shape is ignored, and the mouse events go to the shapes below it.
+    ClickThrough,
+    /// Nobody receives the mouse events, neither the widget that owns the shape nor the shapes
+    /// below it. Useful for the background of modal dialogs.
+    Block,
+}
+
+/// Identifies a button of the mouse.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Identifies a key of the keyboard.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Tab,
+    Enter,
+    Escape,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Space,
+    /// A key that corresponds to a letter or a digit, in lowercase. Used for shortcuts such
+    /// as ctrl-C.
+    Char(char),
+    /// Any other key, identified by a platform-specific code.
+    Other(u32),
+}
+
+/// State of the modifier keys when a key event happened.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
 pub enum Layout {
     AbsolutePositionned(Vec<(Matrix, Arc<Widget>)>),
     /// The content of the widget will be split in parts whose size depend on the weight of each
@@ -193,7 +373,33 @@ pub enum Layout {
         children: Vec<Child>,
         horizontal_align: bool,
     },
+    /// The content of the widget is split in columns and rows. See `Grid`.
+    Grid(Grid),
     Shapes(Vec<Shape>),
+    /// The child is laid out in an area larger than the widget, then moved so that only the part
+    /// designated by `scroll` is visible. Shapes outside of the widget are not drawn and can't
+    /// be clicked.
+    ScrollView {
+        /// The content of the scroll view.
+        child: Arc<Widget>,
+        /// Width and height of the content, relative to the size of the widget. For example
+        /// `[1.0, 3.0]` means that the content is as wide as the widget and three times as high.
+        content_size: [f32; 2],
+        /// Position of the visible area within the content. `[0.0, 0.0]` shows the top-left
+        /// corner of the content and `[1.0, 1.0]` shows the bottom-right corner.
+        scroll: [f32; 2],
+    },
+    /// Same as the inner layout, except that the shapes and children are clipped to the area of
+    /// the widget. Parts that are outside of the widget are not drawn and can't be clicked.
+    Clipped(Box<Layout>),
+    /// Same as the inner layout, except that the colour of the shapes and children is multiplied
+    /// by `tint`. Use `[1.0, 1.0, 1.0, alpha]` to change the opacity of the widget.
+    Tinted {
+        /// RGBA colour to multiply with.
+        tint: [f32; 4],
+        /// The layout to tint.
+        layout: Box<Layout>,
+    },
 }
 
 pub struct Child {
@@ -206,3 +412,31 @@ pub struct Child {
     pub padding_bottom: f32,
     pub padding_left: f32,
 }
+
+/// The content of the widget is split in columns and rows whose size depend on their weight.
+/// Each child occupies one or more cells, and is moved within its cells according to its
+/// `alignment` so that the white spaces around its content are on the opposite side.
+pub struct Grid {
+    /// Weight of each column, from left to right. If the weights don't add up to a positive
+    /// number, all the columns have the same width.
+    pub columns: Vec<i8>,
+    /// Weight of each row, from top to bottom. If the weights don't add up to a positive
+    /// number, all the rows have the same height.
+    pub rows: Vec<i8>,
+    /// List of children. Children whose cells are outside of the grid are ignored.
+    pub children: Vec<GridChild>,
+}
+
+/// A child of a `Layout::Grid`.
+pub struct GridChild {
+    pub child: Arc<Widget>,
+    /// Column of the left-most cell occupied by the child, starting from `0`.
+    pub column: usize,
+    /// Row of the top-most cell occupied by the child, starting from `0`.
+    pub row: usize,
+    /// Number of columns occupied by the child. `0` is treated as `1`.
+    pub column_span: usize,
+    /// Number of rows occupied by the child. `0` is treated as `1`.
+    pub row_span: usize,
+    pub alignment: Alignment,
+}
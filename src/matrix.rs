@@ -44,6 +44,35 @@ impl Matrix {
             [ x,   y,  1.0],
         ])
     }
+
+    /// Returns the inverse of this matrix, or `None` if the matrix can't be inverted.
+    ///
+    /// The inverse can be used to convert viewport coordinates into coordinates relative to
+    /// an object.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let m = self.0;
+
+        let (a, b, c) = (m[0][0], m[0][1], m[0][2]);
+        let (d, e, f) = (m[1][0], m[1][1], m[1][2]);
+        let (g, h, i) = (m[2][0], m[2][1], m[2][2]);
+
+        let co_a = e * i - f * h;
+        let co_b = f * g - d * i;
+        let co_c = d * h - e * g;
+
+        let det = a * co_a + b * co_b + c * co_c;
+        if det == 0.0 {
+            return None;
+        }
+
+        let inv = 1.0 / det;
+
+        Some(Matrix([
+            [co_a * inv, (c * h - b * i) * inv, (b * f - c * e) * inv],
+            [co_b * inv, (a * i - c * g) * inv, (c * d - a * f) * inv],
+            [co_c * inv, (b * g - a * h) * inv, (a * e - b * d) * inv],
+        ]))
+    }
 }
 
 impl ops::Mul for Matrix {
@@ -3,6 +3,7 @@ use HorizontalAlignment;
 use Layout;
 use Matrix;
 use Shape;
+use UvRect;
 use VerticalAlignment;
 use Widget;
 
@@ -58,7 +59,8 @@ impl Widget for Image {
             pos * scale
         };
 
-        let shape = Shape::Image { matrix: matrix, name: self.name.clone() };
+        let shape = Shape::Image { matrix: matrix, name: self.name.clone(), uv: UvRect::full(),
+                                   tint: [1.0, 1.0, 1.0, 1.0] };
         Layout::Shapes(vec![shape])
     }
 }
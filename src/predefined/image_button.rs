@@ -57,8 +57,7 @@ impl Widget for ImageButton {
 
             EventOutcome {
                 refresh_layout: true,
-                propagate_to_parent: true,
-                events_for_parent: Vec::new(),
+                .. Default::default()
             }
 
         } else if let Some(event) = event.downcast_ref::<MouseLeaveEvent>() {
@@ -66,8 +65,7 @@ impl Widget for ImageButton {
 
             EventOutcome {
                 refresh_layout: true,
-                propagate_to_parent: true,
-                events_for_parent: Vec::new(),
+                .. Default::default()
             }
 
         } else {
@@ -2,15 +2,19 @@ use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 
 use Alignment;
-use HorizontalAlignment;
+use FontMetrics;
 use Layout;
-use Matrix;
-use Shape;
-use VerticalAlignment;
+use MonospaceFontMetrics;
+use TextStyle;
 use Widget;
+use text;
+use text::Overflow;
 
 pub struct Label {
     text: String,
+    style: TextStyle,
+    wrap: bool,
+    overflow: Overflow,
     needs_refresh: AtomicBool,
 }
 
@@ -22,6 +26,9 @@ impl Label {
     {
         Label {
             text: text.into(),
+            style: TextStyle::default(),
+            wrap: true,
+            overflow: Overflow::Visible,
             needs_refresh: AtomicBool::new(false),
         }
     }
@@ -31,40 +38,146 @@ impl Label {
         self.text = text.into();
         self.needs_refresh.store(true, Ordering::Relaxed);
     }
+
+    /// Returns the style of the text.
+    #[inline]
+    pub fn style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    /// Changes the style of the text.
+    ///
+    /// If the `size` of the style is `Some`, the text is wrapped to fit the width of the label
+    /// unless wrapping has been disabled with `set_wrap`. If it is `None` (the default), only
+    /// the `\n` characters start new lines and the lines are as high as possible, see
+    /// `set_overflow`.
+    #[inline]
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Changes the `size` of the style of the text. See `set_style`.
+    #[inline]
+    pub fn set_line_height(&mut self, line_height: Option<f32>) {
+        self.style.size = line_height;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Sets whether the text is wrapped when the `size` of its style is `Some`. The default
+    /// is `true`.
+    #[inline]
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Sets what to do with the text that doesn't fit in the label. The default is
+    /// `Overflow::Visible`.
+    ///
+    /// If the `size` of the style is `None`, `Overflow::Visible` shrinks the text until it fits
+    /// in the label. The other policies make the lines fill the height of the label, then
+    /// handle the text that is too wide, so that the text is never smaller than the `min_size`
+    /// of `Overflow::ShrinkToFit`.
+    #[inline]
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
 }
 
 impl Widget for Label {
     #[inline]
     fn build_layout(&self, height_per_width: f32, alignment: Alignment) -> Layout {
-        // TODO: everything here is temporary
+        self.build_layout_with_metrics(height_per_width, alignment,
+                                       &MonospaceFontMetrics::default())
+    }
 
-        let text_ratio = 1.0 / self.text.len() as f32;       // TODO: wrong
+    fn build_layout_with_metrics(&self, height_per_width: f32, alignment: Alignment,
+                                 metrics: &FontMetrics) -> Layout
+    {
+        let style = &self.style;
 
-        let matrix = if height_per_width > text_ratio {
-            let y = match alignment.vertical {
-                VerticalAlignment::Center => 0.0,
-                VerticalAlignment::Top => 1.0 - text_ratio / height_per_width,
-                VerticalAlignment::Bottom => -1.0 + text_ratio / height_per_width,
-            };
+        let metrics_line_height = metrics.line_height(style);
 
-            let scale = Matrix::scale_wh(1.0, text_ratio / height_per_width);
-            let pos = Matrix::translate(0.0, y);
-            pos * scale
+        let (size, wrap) = match style.size {
+            Some(size) => (size, self.wrap),
+            None if self.overflow == Overflow::Visible => {
+                let lines = text::wrap_text(&self.text, None, style, metrics);
+                let widest = lines.iter().fold(0.0, |a, l| if l.width > a { l.width } else { a });
+                return match text::fitting_line_height(lines.len(), widest, metrics_line_height,
+                                                       height_per_width)
+                {
+                    Some(line_height) => {
+                        Layout::Shapes(text::layout_lines(&lines, line_height, height_per_width,
+                                                          alignment, style, metrics))
+                    },
+                    None => Layout::Shapes(Vec::new()),
+                };
+            },
+            None => {
+                // the lines fill the height of the label, and the overflow policy handles
+                // their width
+                match text::wrap_text(&self.text, None, style, metrics).len() {
+                    0 => return Layout::Shapes(Vec::new()),
+                    num_lines => (1.0 / num_lines as f32, false),
+                }
+            },
+        };
 
-        } else {
-            let x = match alignment.horizontal {
-                HorizontalAlignment::Center => 0.0,
-                HorizontalAlignment::Left => -1.0 + height_per_width / text_ratio,
-                HorizontalAlignment::Right => 1.0 - height_per_width / text_ratio,
-            };
-
-            let scale = Matrix::scale_wh(height_per_width / text_ratio, 1.0);
-            let pos = Matrix::translate(x, 0.0);
-            pos * scale
+        let max_width = |line_height| {
+            text::max_line_width(line_height, metrics_line_height, height_per_width)
         };
+        let lines_at = |line_height| {
+            let max_width = if wrap { Some(max_width(line_height)) } else { None };
+            text::wrap_text(&self.text, max_width, style, metrics)
+        };
+        let fits = |line_height: f32, lines: &[text::TextLine]| {
+            lines.len() as f32 * line_height <= 2.0 + 1e-5 &&
+            lines.iter().all(|l| l.width <= max_width(line_height) * (1.0 + 1e-5))
+        };
+
+        let mut line_height = text::size_to_line_height(size);
+        let mut lines = lines_at(line_height);
+
+        if let Overflow::ShrinkToFit { min_size } = self.overflow {
+            let min_line_height = text::size_to_line_height(min_size);
 
-        let shape = Shape::Text { matrix: matrix, text: self.text.clone() };
-        Layout::Shapes(vec![shape])
+            if !fits(line_height, &lines) && min_line_height < line_height {
+                if fits(min_line_height, &lines_at(min_line_height)) {
+                    // the text fits somewhere between the two sizes
+                    let (mut low, mut high) = (min_line_height, line_height);
+                    for _ in 0 .. 16 {
+                        let middle = (low + high) / 2.0;
+                        if fits(middle, &lines_at(middle)) { low = middle; } else { high = middle; }
+                    }
+                    line_height = low;
+                } else {
+                    line_height = min_line_height;
+                }
+
+                lines = lines_at(line_height);
+            }
+        }
+
+        match self.overflow {
+            Overflow::Ellipsis | Overflow::ShrinkToFit { .. } => {
+                let max_lines = (2.0 / line_height + 1e-5).floor() as usize;
+                let max_lines = if max_lines == 0 { 1 } else { max_lines };
+                lines = text::truncate_lines(lines, max_lines, max_width(line_height) * (1.0 + 1e-5),
+                                             style, metrics);
+            },
+            Overflow::Visible | Overflow::Clip => (),
+        }
+
+        let shapes = text::layout_lines(&lines, line_height, height_per_width, alignment, style,
+                                        metrics);
+
+        if self.overflow == Overflow::Clip {
+            Layout::Clipped(Box::new(Layout::Shapes(shapes)))
+        } else {
+            Layout::Shapes(shapes)
+        }
     }
 
     #[inline]
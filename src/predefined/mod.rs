@@ -3,18 +3,211 @@ pub use self::image_button::ImageButton;
 pub use self::image::Image;
 pub use self::label::Label;
 pub use self::nine_slice_image::NineSliceImage;
+pub use self::rich_text::RichText;
+pub use self::scroll_view::ScrollView;
+pub use self::text_input::TextInput;
 pub use self::transition::Transition;
 
+use std::any::Any;
+
+use Key;
+use Modifiers;
+use MouseButton;
+
 #[derive(Copy, Clone, Debug)]
 pub struct MouseEnterEvent;
 #[derive(Copy, Clone, Debug)]
 pub struct MouseLeaveEvent;
+
+/// The cursor has moved over the widget, or anywhere if the widget captured the pointer.
+///
+/// The position is in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct MouseMoveEvent {
+    pub position: [f32; 2],
+}
+
+/// A mouse button has been pressed while the cursor was over the widget.
+///
+/// The position is in the widget's coordinates, where `[-1.0, -1.0]` is the bottom-left corner
+/// and `[1.0, 1.0]` is the top-right corner.
+#[derive(Copy, Clone, Debug)]
+pub struct MouseDownEvent {
+    pub button: MouseButton,
+    pub position: [f32; 2],
+}
+
+/// A mouse button has been released while the cursor was over the widget, or anywhere if the
+/// widget captured the pointer.
+///
+/// The position is in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct MouseUpEvent {
+    pub button: MouseButton,
+    pub position: [f32; 2],
+}
+
+/// A mouse button has been pressed then released over the widget.
+///
+/// The position is in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct MouseClick {
+    pub button: MouseButton,
+    pub position: [f32; 2],
+}
+
+/// The widget has been clicked twice in a short interval. Sent after the second `MouseClick`.
+///
+/// The position is in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct MouseDoubleClick {
+    pub button: MouseButton,
+    pub position: [f32; 2],
+}
+
+/// The mouse wheel or the trackpad has been used while the cursor was over the widget.
+///
+/// A positive `y` means scrolling towards the top of the content, and a positive `x` means
+/// scrolling towards its right. One unit usually corresponds to one notch of the wheel.
+#[derive(Copy, Clone, Debug)]
+pub struct ScrollEvent {
+    pub delta: [f32; 2],
+}
+
+/// The cursor has moved past the drag threshold while the left mouse button was pressed over the
+/// widget. Set `start_drag` in the `EventOutcome` to start a drag and drop.
+///
+/// The position is the one where the button was pressed, in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct DragStartEvent {
+    pub position: [f32; 2],
+}
+
+/// A drag and drop is in progress and the cursor has moved over the widget.
+///
+/// The position is in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct DragMoveEvent {
+    pub position: [f32; 2],
+}
+
+/// A drag and drop has ended over the widget.
+///
+/// The position is in the widget's coordinates.
+#[derive(Debug)]
+pub struct DropEvent {
+    pub position: [f32; 2],
+    /// The payload of the `Drag` returned by the widget where the drag and drop started.
+    pub payload: Box<Any + Send>,
+}
+
+/// The drag and drop started by the widget is over.
+#[derive(Copy, Clone, Debug)]
+pub struct DragEndEvent {
+    /// True if the payload has been dropped on a widget.
+    pub dropped: bool,
+}
+
+/// A finger has touched the widget.
+///
+/// The `id` identifies the touch among the ones in progress. The position is in the widget's
+/// coordinates.
 #[derive(Copy, Clone, Debug)]
-pub struct MouseClick;
+pub struct TouchStartEvent {
+    pub id: u64,
+    pub position: [f32; 2],
+}
+
+/// A finger that started touching the widget has moved, even if it is no longer over the widget.
+///
+/// The position is in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct TouchMoveEvent {
+    pub id: u64,
+    pub position: [f32; 2],
+}
+
+/// A finger that started touching the widget has been lifted.
+///
+/// The position is the last known position of the finger, in the widget's coordinates.
+#[derive(Copy, Clone, Debug)]
+pub struct TouchEndEvent {
+    pub id: u64,
+    pub position: [f32; 2],
+}
+
+/// A key has been pressed while the widget had the focus.
+#[derive(Copy, Clone, Debug)]
+pub struct KeyDownEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+/// A key has been released while the widget had the focus.
+#[derive(Copy, Clone, Debug)]
+pub struct KeyUpEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+/// A character has been typed while the widget had the focus.
+#[derive(Copy, Clone, Debug)]
+pub struct CharacterEvent(pub char);
+
+/// The text being composed with an input method has changed while the widget had the focus.
+///
+/// The text isn't part of the content of the widget yet and should be displayed at the position
+/// of the caret. An empty text means that the composition has been cancelled.
+#[derive(Clone, Debug)]
+pub struct ImePreeditEvent {
+    pub text: String,
+    /// Position of the caret within `text`, as a byte index, or `None` if the caret should be
+    /// hidden.
+    pub cursor: Option<usize>,
+}
+
+/// The text composed with an input method has been validated while the widget had the focus.
+///
+/// The text replaces the text being composed and should be inserted as if it had been typed.
+#[derive(Clone, Debug)]
+pub struct ImeCommitEvent {
+    pub text: String,
+}
+
+/// The user wants to copy the selection of the widget, which had the focus, to the clipboard.
+///
+/// The widget should return the selected text in the `clipboard` field of its `EventOutcome`.
+#[derive(Copy, Clone, Debug)]
+pub struct CopyEvent;
+
+/// Same as `CopyEvent`, except that the selection should also be removed from the widget.
+#[derive(Copy, Clone, Debug)]
+pub struct CutEvent;
+
+/// The user wants to paste the content of the clipboard in the widget, which had the focus.
+#[derive(Clone, Debug)]
+pub struct PasteEvent {
+    pub text: String,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FocusGainedEvent;
+#[derive(Copy, Clone, Debug)]
+pub struct FocusLostEvent;
+
+/// Sent to the parent of a `TextInput` when the user presses `Enter`.
+#[derive(Clone, Debug)]
+pub struct TextSubmittedEvent {
+    /// Content of the text input.
+    pub text: String,
+}
 
 mod empty;
 mod image_button;
 mod image;
 mod label;
 mod nine_slice_image;
+mod rich_text;
+mod scroll_view;
+mod text_input;
 mod transition;
@@ -1,26 +1,75 @@
 use Alignment;
 use Layout;
 use Matrix;
+use Shape;
+use UvRect;
 use Widget;
 
+/// An image split in nine pieces: four corners, four borders and a background. The corners
+/// keep the same size whatever the dimensions of the widget, and the borders and the
+/// background are stretched.
 pub struct NineSliceImage {
-    corner_image: String,
-    border_image: String,
-    background_image: String,
+    source: Source,
     border_width: f32,
     border_height: f32,
 }
 
+enum Source {
+    /// The corner image is the top-left corner and is mirrored for the other corners. The border
+    /// image is the top border and is rotated for the other borders.
+    Separate {
+        corner_image: String,
+        border_image: String,
+        background_image: String,
+    },
+
+    /// A single texture split in a 3x3 grid.
+    Texture {
+        name: String,
+        /// Width and height of the borders in the texture, in texture coordinates.
+        uv_border: [f32; 2],
+    },
+}
+
 impl NineSliceImage {
+    /// Builds an image from three images.
+    ///
+    /// The `corner_image` is the top-left corner and is mirrored to obtain the other corners.
+    /// The `border_image` is the top border and is rotated by quarter turns to obtain the
+    /// other borders, so that its top always faces the outside.
+    ///
+    /// The `border_width` and `border_height` are the half-dimensions of a corner relative to
+    /// the largest dimension of the widget.
     #[inline]
     pub fn new<S1, S2, S3>(corner_image: S1, border_image: S2, background_image: S3,
                            border_width: f32, border_height: f32) -> NineSliceImage
                            where S1: Into<String>, S2: Into<String>, S3: Into<String>
     {
         NineSliceImage {
-            corner_image: corner_image.into(),
-            border_image: border_image.into(),
-            background_image: background_image.into(),
+            source: Source::Separate {
+                corner_image: corner_image.into(),
+                border_image: border_image.into(),
+                background_image: background_image.into(),
+            },
+            border_width: border_width,
+            border_height: border_height,
+        }
+    }
+
+    /// Builds an image from a single texture split in a 3x3 grid, for example a frame of an
+    /// atlas.
+    ///
+    /// `uv_border` contains the width and the height of the borders in texture coordinates.
+    /// The `border_width` and `border_height` are the same as in `new`.
+    #[inline]
+    pub fn from_texture<S>(name: S, uv_border: [f32; 2], border_width: f32, border_height: f32)
+                           -> NineSliceImage where S: Into<String>
+    {
+        NineSliceImage {
+            source: Source::Texture {
+                name: name.into(),
+                uv_border: uv_border,
+            },
             border_width: border_width,
             border_height: border_height,
         }
@@ -30,14 +79,76 @@ impl NineSliceImage {
 impl Widget for NineSliceImage {
     #[inline]
     fn build_layout(&self, height_per_width: f32, _: Alignment) -> Layout {
-        let _corner_scale = if height_per_width > 1.0 {
-            Matrix::scale_wh(height_per_width * self.border_width, self.border_height)
+        let corner_scale = if height_per_width > 1.0 {
+            [height_per_width * self.border_width, self.border_height]
         } else {
-            Matrix::scale_wh(self.border_width, self.border_height / height_per_width)
+            [self.border_width, self.border_height / height_per_width]
         };
 
-        unimplemented!()        // TODO: 
+        // the corners can't be larger than the widget
+        let cw = corner_scale[0].min(1.0);
+        let ch = corner_scale[1].min(1.0);
+
+        // position of the center of the pieces and half-size of the stretched parts
+        let (left, right, top, bottom) = (-1.0 + cw, 1.0 - cw, 1.0 - ch, -1.0 + ch);
+        let (stretch_w, stretch_h) = (1.0 - cw, 1.0 - ch);
+
+        // quarter turns, counter-clockwise
+        let quarter = Matrix([[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+        let half = Matrix([[-1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]]);
+        let three_quarters = Matrix([[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+
+        let shapes = match self.source {
+            Source::Separate { ref corner_image, ref border_image, ref background_image } => {
+                let pieces = vec![
+                    (background_image, Matrix::scale_wh(stretch_w, stretch_h)),
+
+                    (border_image, Matrix::translate(0.0, top) * Matrix::scale_wh(stretch_w, ch)),
+                    (border_image, Matrix::translate(left, 0.0) * quarter *
+                                   Matrix::scale_wh(stretch_h, cw)),
+                    (border_image, Matrix::translate(0.0, bottom) * half *
+                                   Matrix::scale_wh(stretch_w, ch)),
+                    (border_image, Matrix::translate(right, 0.0) * three_quarters *
+                                   Matrix::scale_wh(stretch_h, cw)),
+
+                    (corner_image, Matrix::translate(left, top) * Matrix::scale_wh(cw, ch)),
+                    (corner_image, Matrix::translate(right, top) * Matrix::scale_wh(-cw, ch)),
+                    (corner_image, Matrix::translate(left, bottom) * Matrix::scale_wh(cw, -ch)),
+                    (corner_image, Matrix::translate(right, bottom) * Matrix::scale_wh(-cw, -ch)),
+                ];
+
+                pieces.into_iter().map(|(name, matrix)| {
+                    Shape::Image { matrix: matrix, name: name.clone(), uv: UvRect::full(),
+                                   tint: [1.0, 1.0, 1.0, 1.0] }
+                }).collect()
+            },
+
+            Source::Texture { ref name, uv_border } => {
+                let (u, v) = (uv_border[0], uv_border[1]);
+
+                // column and row of each piece in the texture and in the widget
+                let columns = [(0.0, u, left, cw), (u, 1.0 - u, 0.0, stretch_w),
+                               (1.0 - u, 1.0, right, cw)];
+                let rows = [(1.0 - v, 1.0, top, ch), (v, 1.0 - v, 0.0, stretch_h),
+                            (0.0, v, bottom, ch)];
+
+                // the background, then the borders, then the corners
+                let order = [(1, 1), (1, 0), (0, 1), (1, 2), (2, 1), (0, 0), (2, 0), (0, 2), (2, 2)];
+
+                order.iter().map(|&(col, row)| {
+                    let (uv_left, uv_right, x, w) = columns[col];
+                    let (uv_bottom, uv_top, y, h) = rows[row];
+
+                    Shape::Image {
+                        matrix: Matrix::translate(x, y) * Matrix::scale_wh(w, h),
+                        name: name.clone(),
+                        uv: UvRect { top: uv_top, right: uv_right, bottom: uv_bottom, left: uv_left },
+                        tint: [1.0, 1.0, 1.0, 1.0],
+                    }
+                }).collect()
+            },
+        };
 
-        //let vertical_border_scale = ;
+        Layout::Shapes(shapes)
     }
 }
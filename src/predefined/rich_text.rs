@@ -0,0 +1,92 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use Alignment;
+use FontMetrics;
+use Layout;
+use MonospaceFontMetrics;
+use Widget;
+use text;
+use text::Span;
+
+/// A paragraph made of several spans of text with different styles, and of images.
+pub struct RichText {
+    spans: Vec<Span>,
+    line_height: Option<f32>,
+    needs_refresh: AtomicBool,
+}
+
+impl RichText {
+    /// Initializes a new rich text.
+    #[inline]
+    pub fn new(spans: Vec<Span>) -> RichText {
+        RichText {
+            spans: spans,
+            line_height: None,
+            needs_refresh: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the spans of the text.
+    #[inline]
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    #[inline]
+    pub fn set_spans(&mut self, spans: Vec<Span>) {
+        self.spans = spans;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Same as the `size` of the style of a `Label`. If `Some`, the spans are wrapped to fit
+    /// the width of the widget and each line has this height, relative to the height of the
+    /// widget. If `None` (the default), the text is as large as possible.
+    #[inline]
+    pub fn set_line_height(&mut self, line_height: Option<f32>) {
+        self.line_height = line_height;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Widget for RichText {
+    #[inline]
+    fn build_layout(&self, height_per_width: f32, alignment: Alignment) -> Layout {
+        self.build_layout_with_metrics(height_per_width, alignment,
+                                       &MonospaceFontMetrics::default())
+    }
+
+    fn build_layout_with_metrics(&self, height_per_width: f32, alignment: Alignment,
+                                 metrics: &FontMetrics) -> Layout
+    {
+        let metrics_line_height = text::spans_line_height(&self.spans, metrics);
+
+        let (lines, line_height) = match self.line_height {
+            Some(line_height) => {
+                let line_height = text::size_to_line_height(line_height);
+                let max_width = text::max_line_width(line_height, metrics_line_height,
+                                                     height_per_width);
+                (text::wrap_spans(&self.spans, Some(max_width), metrics), line_height)
+            },
+
+            None => {
+                let lines = text::wrap_spans(&self.spans, None, metrics);
+                let widest = lines.iter().fold(0.0, |a, l| if l.width > a { l.width } else { a });
+                match text::fitting_line_height(lines.len(), widest, metrics_line_height,
+                                                height_per_width)
+                {
+                    Some(line_height) => (lines, line_height),
+                    None => return Layout::Shapes(Vec::new()),
+                }
+            },
+        };
+
+        Layout::Shapes(text::layout_span_lines(&self.spans, &lines, line_height,
+                                               height_per_width, alignment, metrics))
+    }
+
+    #[inline]
+    fn needs_rebuild(&self) -> bool {
+        self.needs_refresh.swap(false, Ordering::Relaxed)
+    }
+}
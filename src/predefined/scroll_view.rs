@@ -0,0 +1,105 @@
+use std::any::Any;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use Alignment;
+use EventOutcome;
+use Layout;
+use Widget;
+
+use predefined::ScrollEvent;
+
+/// A widget whose content is larger than itself and can be scrolled with the mouse wheel.
+pub struct ScrollView<W> {
+    child: Arc<W>,
+    content_size: [f32; 2],
+    scroll: Mutex<[f32; 2]>,
+    speed: f32,
+}
+
+impl<W> ScrollView<W> where W: Widget {
+    /// Builds a new scroll view showing the top-left corner of its content.
+    ///
+    /// The `content_width` and `content_height` are relative to the size of the scroll view.
+    #[inline]
+    pub fn new(child: Arc<W>, content_width: f32, content_height: f32) -> ScrollView<W> {
+        ScrollView {
+            child: child,
+            content_size: [content_width, content_height],
+            scroll: Mutex::new([0.0, 0.0]),
+            speed: 0.1,
+        }
+    }
+
+    /// Returns the position of the visible area within the content, between `[0.0, 0.0]`
+    /// (top-left) and `[1.0, 1.0]` (bottom-right).
+    #[inline]
+    pub fn scroll_position(&self) -> [f32; 2] {
+        *self.scroll.lock().unwrap()
+    }
+
+    /// Changes the position of the visible area. The values are clamped between `0.0` and `1.0`.
+    ///
+    /// Note that the layout isn't refreshed automatically.
+    #[inline]
+    pub fn set_scroll_position(&self, position: [f32; 2]) {
+        *self.scroll.lock().unwrap() = [position[0].clamp(0.0, 1.0),
+                                         position[1].clamp(0.0, 1.0)];
+    }
+
+    /// Sets the portion of the scroll view's size that one unit of scrolling moves the content
+    /// by. The default is `0.1`.
+    #[inline]
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Returns the content of the scroll view.
+    #[inline]
+    pub fn child(&self) -> &Arc<W> {
+        &self.child
+    }
+}
+
+impl<W> Widget for ScrollView<W> where W: Widget {
+    #[inline]
+    fn build_layout(&self, _: f32, _: Alignment) -> Layout {
+        Layout::ScrollView {
+            child: self.child.clone(),
+            content_size: self.content_size,
+            scroll: self.scroll_position(),
+        }
+    }
+
+    fn handle_event(&self, event: &Any, _: Option<usize>) -> EventOutcome {
+        let event = match event.downcast_ref::<ScrollEvent>() {
+            Some(ev) => ev,
+            None => return Default::default(),
+        };
+
+        let mut scroll = self.scroll.lock().unwrap();
+        let old = *scroll;
+
+        // a scroll of `1.0` moves the content by the size of the scroll view, which is
+        // `1 / (content_size - 1)` of the scrolling range
+        if self.content_size[0] > 1.0 {
+            scroll[0] = (scroll[0] + event.delta[0] * self.speed / (self.content_size[0] - 1.0))
+                            .clamp(0.0, 1.0);
+        }
+        if self.content_size[1] > 1.0 {
+            scroll[1] = (scroll[1] - event.delta[1] * self.speed / (self.content_size[1] - 1.0))
+                            .clamp(0.0, 1.0);
+        }
+
+        if *scroll == old {
+            // nothing to scroll, so the parent may want to scroll instead
+            return Default::default();
+        }
+
+        EventOutcome {
+            refresh_layout: true,
+            propagate_to_parent: false,
+            .. Default::default()
+        }
+    }
+}
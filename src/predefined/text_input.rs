@@ -0,0 +1,562 @@
+use std::any::Any;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use time;
+
+use Alignment;
+use EventOutcome;
+use FontMetrics;
+use Key;
+use Layout;
+use Matrix;
+use MonospaceFontMetrics;
+use MouseButton;
+use Shape;
+use TextStyle;
+use VerticalAlignment;
+use Widget;
+use text;
+
+use predefined::CharacterEvent;
+use predefined::CopyEvent;
+use predefined::CutEvent;
+use predefined::FocusGainedEvent;
+use predefined::FocusLostEvent;
+use predefined::ImeCommitEvent;
+use predefined::ImePreeditEvent;
+use predefined::KeyDownEvent;
+use predefined::MouseDownEvent;
+use predefined::MouseMoveEvent;
+use predefined::MouseUpEvent;
+use predefined::PasteEvent;
+use predefined::TextSubmittedEvent;
+
+/// A single line of editable text.
+///
+/// The widget must have the focus to receive keyboard input. Clicking it gives it the focus.
+/// When the user presses `Enter`, a `TextSubmittedEvent` is sent to the parent. The selection
+/// can be copied to and replaced with the content of the clipboard of the `Ui`.
+///
+/// Text composed with an input method is displayed underlined in place of the selection until
+/// it is committed.
+pub struct TextInput {
+    state: Mutex<State>,
+    style: TextStyle,
+    selection_color: [f32; 4],
+    blink_interval_ns: u64,
+    needs_refresh: AtomicBool,
+}
+
+struct State {
+    text: String,
+    /// Position of the caret, as a byte index in `text`.
+    caret: usize,
+    /// Other end of the selection, as a byte index in `text`. The selection is empty if it is
+    /// equal to `caret`.
+    anchor: usize,
+    /// Width of the text that is hidden on the left, in metrics units.
+    scroll: f32,
+    focused: bool,
+    /// True while the left button is held after clicking in the widget.
+    selecting: bool,
+    /// Time when the caret was last moved, used to compute the blinking.
+    blink_start_ns: u64,
+    /// Whether the caret was visible during the last layout.
+    caret_shown: bool,
+    /// Text being composed with an input method, displayed in place of the selection.
+    preedit: String,
+    /// Position of the caret within `preedit`, or `None` if the caret is hidden.
+    preedit_cursor: Option<usize>,
+    /// Rectangle of the caret during the last layout, in the widget's coordinates.
+    caret_rect: Matrix,
+    /// Positions of the character boundaries during the last layout, in the widget's
+    /// coordinates. Used to place the caret when clicking.
+    boundaries: Vec<(usize, f32)>,
+}
+
+impl TextInput {
+    /// Initializes a new text input containing `text`, with the caret at the end.
+    #[inline]
+    pub fn new<S>(text: S) -> TextInput where S: Into<String> {
+        let text = text.into();
+        let len = text.len();
+
+        TextInput {
+            state: Mutex::new(State {
+                text: text,
+                caret: len,
+                anchor: len,
+                scroll: 0.0,
+                focused: false,
+                selecting: false,
+                blink_start_ns: time::precise_time_ns(),
+                caret_shown: false,
+                preedit: String::new(),
+                preedit_cursor: None,
+                caret_rect: Matrix::identity(),
+                boundaries: Vec::new(),
+            }),
+            style: TextStyle::default(),
+            selection_color: [0.2, 0.4, 1.0, 0.5],
+            blink_interval_ns: 500_000_000,
+            needs_refresh: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the content of the text input.
+    #[inline]
+    pub fn text(&self) -> String {
+        self.state.lock().unwrap().text.clone()
+    }
+
+    /// Replaces the content of the text input and moves the caret to the end.
+    #[inline]
+    pub fn set_text<S>(&self, text: S) where S: Into<String> {
+        let mut state = self.state.lock().unwrap();
+        state.text = text.into();
+        let len = state.text.len();
+        state.move_caret(len, false);
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the position of the caret, as a byte index in the text.
+    #[inline]
+    pub fn caret(&self) -> usize {
+        self.state.lock().unwrap().caret
+    }
+
+    /// Returns the byte range of the selected text. The range is empty if nothing is selected.
+    #[inline]
+    pub fn selection(&self) -> (usize, usize) {
+        self.state.lock().unwrap().selection()
+    }
+
+    /// Selects the text between the byte indices `start` and `end`, and puts the caret at `end`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if one of the indices isn't on a character boundary.
+    pub fn set_selection(&self, start: usize, end: usize) {
+        let mut state = self.state.lock().unwrap();
+        assert!(state.text.is_char_boundary(start) && state.text.is_char_boundary(end));
+        state.anchor = start;
+        state.caret = end;
+        state.blink_start_ns = time::precise_time_ns();
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the style of the text.
+    #[inline]
+    pub fn style(&self) -> &TextStyle {
+        &self.style
+    }
+
+    /// Changes the style of the text. If the `size` is `None` (the default), the text is as
+    /// high as the widget.
+    #[inline]
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Sets the color of the rectangle drawn behind the selected text.
+    #[inline]
+    pub fn set_selection_color(&mut self, color: [f32; 4]) {
+        self.selection_color = color;
+        self.needs_refresh.store(true, Ordering::Relaxed);
+    }
+
+    /// Sets the duration in nanoseconds during which the caret is visible, then invisible.
+    /// The default is 500ms.
+    #[inline]
+    pub fn set_blink_interval(&mut self, interval_ns: u64) {
+        self.blink_interval_ns = interval_ns;
+    }
+
+    /// Returns true if the caret is in the visible phase of its blinking.
+    fn caret_visible(&self, state: &State) -> bool {
+        if !state.focused {
+            return false;
+        }
+
+        if self.blink_interval_ns == 0 {
+            return true;
+        }
+
+        let elapsed = time::precise_time_ns().saturating_sub(state.blink_start_ns);
+        (elapsed / self.blink_interval_ns).is_multiple_of(2)
+    }
+}
+
+impl State {
+    #[inline]
+    fn selection(&self) -> (usize, usize) {
+        if self.anchor <= self.caret {
+            (self.anchor, self.caret)
+        } else {
+            (self.caret, self.anchor)
+        }
+    }
+
+    /// Moves the caret to `position`. If `extend` is false, the selection is cleared.
+    fn move_caret(&mut self, position: usize, extend: bool) {
+        self.caret = position;
+        if !extend {
+            self.anchor = position;
+        }
+        self.blink_start_ns = time::precise_time_ns();
+    }
+
+    /// Replaces the selected text with `text`.
+    fn replace_selection(&mut self, text: &str) {
+        let (start, end) = self.selection();
+        self.text = format!("{}{}{}", &self.text[.. start], text, &self.text[end ..]);
+        self.move_caret(start + text.len(), false);
+    }
+
+    fn previous_boundary(&self, position: usize) -> usize {
+        self.text[.. position].char_indices().next_back().map(|(i, _)| i).unwrap_or(0)
+    }
+
+    fn next_boundary(&self, position: usize) -> usize {
+        self.text[position ..].chars().next().map(|c| position + c.len_utf8())
+                                               .unwrap_or(position)
+    }
+
+    /// Returns the character boundary closest to the horizontal position `x`.
+    fn boundary_at(&self, x: f32) -> usize {
+        let mut best = (self.text.len(), f32::INFINITY);
+        // the text may have changed since the last layout
+        let valid = self.boundaries.iter().filter(|&&(i, _)| self.text.is_char_boundary(i));
+        for &(index, pos) in valid {
+            let distance = (pos - x).abs();
+            if distance < best.1 {
+                best = (index, distance);
+            }
+        }
+        best.0
+    }
+}
+
+impl Widget for TextInput {
+    #[inline]
+    fn build_layout(&self, height_per_width: f32, alignment: Alignment) -> Layout {
+        self.build_layout_with_metrics(height_per_width, alignment,
+                                       &MonospaceFontMetrics::default())
+    }
+
+    fn build_layout_with_metrics(&self, height_per_width: f32, alignment: Alignment,
+                                 metrics: &FontMetrics) -> Layout
+    {
+        let mut state = self.state.lock().unwrap();
+        let style = &self.style;
+
+        let line_height = text::size_to_line_height(style.size.unwrap_or(1.0));
+        let metrics_line_height = metrics.line_height(style);
+        let visible_width = text::max_line_width(line_height, metrics_line_height,
+                                                 height_per_width);
+        // converts metrics units into the widget's coordinates
+        let scale = line_height * height_per_width / metrics_line_height;
+        let caret_width = metrics_line_height * 0.08;
+
+        // the text being composed with an input method replaces the selection until it is
+        // committed
+        let composing = !state.preedit.is_empty();
+        let (start, end) = if composing { state.selection() } else { (state.caret, state.caret) };
+        let preedit_end = start + state.preedit.len();
+        let displayed = format!("{}{}{}", &state.text[.. start], state.preedit,
+                                &state.text[end ..]);
+        // converts a byte index in the text into a byte index in `displayed`
+        let displayed_index = |i: usize| {
+            if i <= start { i } else if i >= end { i - end + preedit_end } else { start }
+        };
+        let caret = if composing {
+            start + state.preedit_cursor.unwrap_or(state.preedit.len())
+        } else {
+            state.caret
+        };
+
+        // scrolling so that the caret stays visible
+        let caret_x = metrics.text_width(&displayed[.. caret], style);
+        let text_width = metrics.text_width(&displayed, style);
+        if caret_x < state.scroll {
+            state.scroll = caret_x;
+        }
+        if caret_x + caret_width > state.scroll + visible_width {
+            state.scroll = caret_x + caret_width - visible_width;
+        }
+        let max_scroll = text_width + caret_width - visible_width;
+        if state.scroll > max_scroll {
+            state.scroll = max_scroll;
+        }
+        if state.scroll < 0.0 {
+            state.scroll = 0.0;
+        }
+
+        let to_widget = |x: f32| -1.0 + (x - state.scroll) * scale;
+        let x_of = |i: usize| to_widget(metrics.text_width(&displayed[.. i], style));
+        let y = match alignment.vertical {
+            VerticalAlignment::Center => 0.0,
+            VerticalAlignment::Top => 1.0 - line_height / 2.0,
+            VerticalAlignment::Bottom => -1.0 + line_height / 2.0,
+        };
+
+        let boundaries = state.text.char_indices().map(|(i, _)| i)
+                              .chain(Some(state.text.len()))
+                              .map(|i| (i, x_of(displayed_index(i))))
+                              .collect();
+
+        // an invisible background, so that the whole widget can be clicked
+        let mut shapes = vec![
+            Shape::Rectangle { matrix: Matrix::identity(), color: [0.0, 0.0, 0.0, 0.0] }
+        ];
+
+        let (sel_start, sel_end) = state.selection();
+        if sel_start != sel_end && !composing {
+            let (left, right) = (x_of(sel_start), x_of(sel_end));
+            shapes.push(Shape::Rectangle {
+                matrix: Matrix::translate((left + right) / 2.0, y) *
+                        Matrix::scale_wh((right - left) / 2.0, line_height / 2.0),
+                color: self.selection_color,
+            });
+        }
+
+        if !displayed.is_empty() {
+            let half_width = text_width * scale / 2.0;
+            shapes.push(Shape::Text {
+                matrix: Matrix::translate(to_widget(0.0) + half_width, y) *
+                        Matrix::scale_wh(half_width, line_height / 2.0),
+                text: displayed.clone(),
+                style: style.clone(),
+                tint: [1.0, 1.0, 1.0, 1.0],
+            });
+        }
+
+        if composing {
+            // underlining the text being composed
+            let (left, right) = (x_of(start), x_of(preedit_end));
+            let half_thickness = line_height * 0.04;
+            shapes.push(Shape::Rectangle {
+                matrix: Matrix::translate((left + right) / 2.0,
+                                          y - line_height / 2.0 + half_thickness) *
+                        Matrix::scale_wh((right - left) / 2.0, half_thickness),
+                color: style.color,
+            });
+        }
+
+        let caret_rect = {
+            let half_width = caret_width * scale / 2.0;
+            Matrix::translate(to_widget(caret_x) + half_width, y) *
+            Matrix::scale_wh(half_width, line_height / 2.0)
+        };
+
+        let caret_shown = self.caret_visible(&state);
+        if caret_shown && (!composing || state.preedit_cursor.is_some()) {
+            shapes.push(Shape::Rectangle { matrix: caret_rect, color: style.color });
+        }
+
+        state.caret_shown = caret_shown;
+        state.caret_rect = caret_rect;
+        state.boundaries = boundaries;
+
+        Layout::Clipped(Box::new(Layout::Shapes(shapes)))
+    }
+
+    #[inline]
+    fn needs_rebuild(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        let blinked = self.caret_visible(&state) != state.caret_shown;
+        self.needs_refresh.swap(false, Ordering::Relaxed) || blinked
+    }
+
+    #[inline]
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    #[inline]
+    fn caret_rect(&self) -> Option<Matrix> {
+        let state = self.state.lock().unwrap();
+        if state.focused { Some(state.caret_rect) } else { None }
+    }
+
+    fn handle_event(&self, event: &Any, _: Option<usize>) -> EventOutcome {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(&MouseDownEvent { button: MouseButton::Left, position }) = event.downcast_ref() {
+            let position = state.boundary_at(position[0]);
+            state.move_caret(position, false);
+            state.selecting = true;
+            return EventOutcome {
+                refresh_layout: true,
+                propagate_to_parent: false,
+                request_focus: true,
+                capture_pointer: true,
+                .. Default::default()
+            };
+        }
+
+        if let Some(&MouseMoveEvent { position, .. }) = event.downcast_ref() {
+            if state.selecting {
+                let position = state.boundary_at(position[0]);
+                if position != state.caret {
+                    state.move_caret(position, true);
+                    return EventOutcome { refresh_layout: true, .. Default::default() };
+                }
+            }
+            return Default::default();
+        }
+
+        if let Some(&MouseUpEvent { button: MouseButton::Left, .. }) = event.downcast_ref() {
+            state.selecting = false;
+            return Default::default();
+        }
+
+        if event.is::<FocusGainedEvent>() {
+            state.focused = true;
+            state.blink_start_ns = time::precise_time_ns();
+            return EventOutcome { refresh_layout: true, .. Default::default() };
+        }
+
+        if event.is::<FocusLostEvent>() {
+            state.focused = false;
+            state.selecting = false;
+            state.preedit.clear();
+            return EventOutcome { refresh_layout: true, .. Default::default() };
+        }
+
+        if let Some(ev) = event.downcast_ref::<ImePreeditEvent>() {
+            state.preedit = ev.text.clone();
+            state.preedit_cursor = ev.cursor.map(|c| {
+                if ev.text.is_char_boundary(c) { c } else { ev.text.len() }
+            });
+            state.blink_start_ns = time::precise_time_ns();
+            return EventOutcome {
+                refresh_layout: true,
+                propagate_to_parent: false,
+                .. Default::default()
+            };
+        }
+
+        if let Some(ev) = event.downcast_ref::<ImeCommitEvent>() {
+            state.preedit.clear();
+            state.replace_selection(&ev.text);
+            return EventOutcome {
+                refresh_layout: true,
+                propagate_to_parent: false,
+                .. Default::default()
+            };
+        }
+
+        if event.is::<CopyEvent>() || event.is::<CutEvent>() {
+            let (start, end) = state.selection();
+            if start == end {
+                return Default::default();
+            }
+
+            let selected = state.text[start .. end].to_owned();
+            let cut = event.is::<CutEvent>();
+            if cut {
+                state.replace_selection("");
+            }
+
+            return EventOutcome {
+                refresh_layout: cut,
+                propagate_to_parent: false,
+                clipboard: Some(selected),
+                .. Default::default()
+            };
+        }
+
+        if let Some(ev) = event.downcast_ref::<PasteEvent>() {
+            // the text input only has one line
+            let text: String = ev.text.chars().filter(|c| !c.is_control()).collect();
+            state.replace_selection(&text);
+            return EventOutcome {
+                refresh_layout: true,
+                propagate_to_parent: false,
+                .. Default::default()
+            };
+        }
+
+        if let Some(&CharacterEvent(character)) = event.downcast_ref() {
+            if character.is_control() {
+                return Default::default();
+            }
+
+            let mut buffer = [0; 4];
+            state.replace_selection(character.encode_utf8(&mut buffer));
+            return EventOutcome {
+                refresh_layout: true,
+                propagate_to_parent: false,
+                .. Default::default()
+            };
+        }
+
+        let &KeyDownEvent { key, modifiers } = match event.downcast_ref() {
+            Some(ev) => ev,
+            None => return Default::default(),
+        };
+
+        let (sel_start, sel_end) = state.selection();
+        let has_selection = sel_start != sel_end;
+
+        match key {
+            Key::Left => {
+                let position = if has_selection && !modifiers.shift {
+                    sel_start
+                } else {
+                    state.previous_boundary(state.caret)
+                };
+                state.move_caret(position, modifiers.shift);
+            },
+            Key::Right => {
+                let position = if has_selection && !modifiers.shift {
+                    sel_end
+                } else {
+                    state.next_boundary(state.caret)
+                };
+                state.move_caret(position, modifiers.shift);
+            },
+            Key::Home => state.move_caret(0, modifiers.shift),
+            Key::End => {
+                let len = state.text.len();
+                state.move_caret(len, modifiers.shift);
+            },
+            Key::Backspace => {
+                if !has_selection {
+                    state.anchor = state.previous_boundary(state.caret);
+                }
+                state.replace_selection("");
+            },
+            Key::Delete => {
+                if !has_selection {
+                    state.anchor = state.next_boundary(state.caret);
+                }
+                state.replace_selection("");
+            },
+            Key::Char('a') | Key::Char('A') if modifiers.ctrl => {
+                let len = state.text.len();
+                state.anchor = 0;
+                state.move_caret(len, true);
+            },
+            Key::Enter => {
+                return EventOutcome {
+                    propagate_to_parent: false,
+                    events_for_parent: vec![Box::new(TextSubmittedEvent {
+                        text: state.text.clone(),
+                    })],
+                    .. Default::default()
+                };
+            },
+            _ => return Default::default(),
+        }
+
+        EventOutcome {
+            refresh_layout: true,
+            propagate_to_parent: false,
+            .. Default::default()
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use Matrix;
+use TextStyle;
 
 /// A shape that can be drawn by any of the UI's components.
 ///
@@ -10,10 +11,62 @@ pub enum Shape {
     Text {
         matrix: Matrix,
         text: String,
+        /// Font, colour and decorations of the text.
+        style: TextStyle,
+        /// RGBA colour that multiplies the colour of the text. Use `[1.0, 1.0, 1.0, 1.0]` to
+        /// draw the text unchanged, and the alpha component to change its opacity.
+        tint: [f32; 4],
     },
     Image {
         matrix: Matrix,
         name: String,
+        /// Region of the texture to draw. If `name` is a frame of the `Atlas` of the `Ui`, the
+        /// region is relative to the frame.
+        uv: UvRect,
+        /// RGBA colour that multiplies the colour of the image. Use `[1.0, 1.0, 1.0, 1.0]` to
+        /// draw the image unchanged, and the alpha component to change its opacity.
+        tint: [f32; 4],
+    },
+    /// A rectangle filled with a solid colour.
+    Rectangle {
+        matrix: Matrix,
+        /// RGBA colour of the rectangle.
+        color: [f32; 4],
+    },
+    /// A rectangle with rounded corners filled with a solid colour.
+    RoundedRectangle {
+        matrix: Matrix,
+        /// RGBA colour of the rectangle.
+        color: [f32; 4],
+        /// Radius of the corners, in the coordinates of the shape (where the rectangle goes
+        /// from `-1.0` to `1.0`). Should be between `0.0` and `1.0`.
+        radius: f32,
+    },
+    /// An ellipse filled with a solid colour. The ellipse touches the four borders of the
+    /// square from `-1.0` to `1.0`.
+    Ellipse {
+        matrix: Matrix,
+        /// RGBA colour of the ellipse.
+        color: [f32; 4],
+    },
+    /// Line segments going through a list of points. Two points make a single segment.
+    Line {
+        matrix: Matrix,
+        /// RGBA colour of the line.
+        color: [f32; 4],
+        /// Points of the line, in the coordinates of the shape.
+        points: Vec<[f32; 2]>,
+        /// Thickness of the line, in the coordinates of the shape.
+        thickness: f32,
+    },
+    /// A polygon filled with a solid colour. The polygon is automatically closed and can be
+    /// concave. Self-intersecting polygons use the even-odd rule.
+    Polygon {
+        matrix: Matrix,
+        /// RGBA colour of the polygon.
+        color: [f32; 4],
+        /// Vertices of the polygon, in the coordinates of the shape.
+        points: Vec<[f32; 2]>,
     },
 }
 
@@ -21,56 +74,111 @@ impl Shape {
     #[inline]
     pub fn apply_matrix(self, outer: &Matrix) -> Shape {
         match self {
-            Shape::Text { matrix, text } => Shape::Text { matrix: *outer * matrix, text: text },
-            Shape::Image { matrix, name } => Shape::Image { matrix: *outer * matrix, name: name },
+            Shape::Text { matrix, text, style, tint } => {
+                Shape::Text { matrix: *outer * matrix, text: text, style: style, tint: tint }
+            },
+            Shape::Image { matrix, name, uv, tint } => {
+                Shape::Image { matrix: *outer * matrix, name: name, uv: uv, tint: tint }
+            },
+            Shape::Rectangle { matrix, color } => {
+                Shape::Rectangle { matrix: *outer * matrix, color: color }
+            },
+            Shape::RoundedRectangle { matrix, color, radius } => {
+                Shape::RoundedRectangle { matrix: *outer * matrix, color: color, radius: radius }
+            },
+            Shape::Ellipse { matrix, color } => {
+                Shape::Ellipse { matrix: *outer * matrix, color: color }
+            },
+            Shape::Line { matrix, color, points, thickness } => {
+                Shape::Line { matrix: *outer * matrix, color: color, points: points,
+                              thickness: thickness }
+            },
+            Shape::Polygon { matrix, color, points } => {
+                Shape::Polygon { matrix: *outer * matrix, color: color, points: points }
+            },
         }
     }
 
-    /// Returns the bounding box of the shape.
-    ///
-    /// Order: top, right, bottom, left. Each value is between `-1.0` and `1.0`.
-    /// The top will always be greater than the bottom. The right will always be greater than
-    /// the left.
-    pub fn get_bounding_box(&self) -> (f32, f32, f32, f32) {
-        macro_rules! min {
-            ($f:expr, $($o:expr),+) => ({
-                let other = min!($($o),+);
-                if $f < other { $f } else { other }
-            });
-            ($f:expr) => ($f);
+    /// Multiplies the colour or the tint of the shape by `tint`.
+    pub fn apply_tint(self, tint: &[f32; 4]) -> Shape {
+        fn mul(a: [f32; 4], b: &[f32; 4]) -> [f32; 4] {
+            [a[0] * b[0], a[1] * b[1], a[2] * b[2], a[3] * b[3]]
         }
 
-        macro_rules! max {
-            ($f:expr, $($o:expr),+) => ({
-                let other = max!($($o),+);
-                if $f > other { $f } else { other }
-            });
-            ($f:expr) => ($f);
+        match self {
+            Shape::Text { matrix, text, style, tint: t } => {
+                Shape::Text { matrix: matrix, text: text, style: style, tint: mul(t, tint) }
+            },
+            Shape::Image { matrix, name, uv, tint: t } => {
+                Shape::Image { matrix: matrix, name: name, uv: uv, tint: mul(t, tint) }
+            },
+            Shape::Rectangle { matrix, color } => {
+                Shape::Rectangle { matrix: matrix, color: mul(color, tint) }
+            },
+            Shape::RoundedRectangle { matrix, color, radius } => {
+                Shape::RoundedRectangle { matrix: matrix, color: mul(color, tint), radius: radius }
+            },
+            Shape::Ellipse { matrix, color } => {
+                Shape::Ellipse { matrix: matrix, color: mul(color, tint) }
+            },
+            Shape::Line { matrix, color, points, thickness } => {
+                Shape::Line { matrix: matrix, color: mul(color, tint), points: points,
+                              thickness: thickness }
+            },
+            Shape::Polygon { matrix, color, points } => {
+                Shape::Polygon { matrix: matrix, color: mul(color, tint), points: points }
+            },
         }
+    }
 
-        let matrix = match self {
+    /// Returns the matrix of the shape.
+    #[inline]
+    pub fn matrix(&self) -> &Matrix {
+        match self {
             &Shape::Text { ref matrix, .. } => matrix,
             &Shape::Image { ref matrix, .. } => matrix,
-        };
-
-        let top_left = *matrix * [-1.0, 1.0, 1.0];
-        let top_left = [top_left[0] / top_left[2], top_left[1] / top_left[2]];
+            &Shape::Rectangle { ref matrix, .. } => matrix,
+            &Shape::RoundedRectangle { ref matrix, .. } => matrix,
+            &Shape::Ellipse { ref matrix, .. } => matrix,
+            &Shape::Line { ref matrix, .. } => matrix,
+            &Shape::Polygon { ref matrix, .. } => matrix,
+        }
+    }
 
-        let top_right = *matrix * [1.0, 1.0, 1.0];
-        let top_right = [top_right[0] / top_right[2], top_right[1] / top_right[2]];
+    /// Returns the bounding box of the shape.
+    ///
+    /// Order: top, right, bottom, left. Each value is between `-1.0` and `1.0`.
+    /// The top will always be greater than the bottom. The right will always be greater than
+    /// the left.
+    pub fn get_bounding_box(&self) -> (f32, f32, f32, f32) {
+        match self {
+            &Shape::Ellipse { ref matrix, .. } => {
+                // the extents of an ellipse are the lengths of the rows of the matrix
+                let m = matrix.0;
+                let center = [m[2][0] / m[2][2], m[2][1] / m[2][2]];
+                let half_width = (m[0][0] * m[0][0] + m[1][0] * m[1][0]).sqrt() / m[2][2];
+                let half_height = (m[0][1] * m[0][1] + m[1][1] * m[1][1]).sqrt() / m[2][2];
+                (center[1] + half_height, center[0] + half_width,
+                 center[1] - half_height, center[0] - half_width)
+            },
 
-        let bot_left = *matrix * [-1.0, -1.0, 1.0];
-        let bot_left = [bot_left[0] / bot_left[2], bot_left[1] / bot_left[2]];
+            &Shape::Line { ref matrix, ref points, thickness, .. } => {
+                // each point is surrounded by a square whose side is the thickness of the line,
+                // which contains the ends of the line whatever their orientation
+                let t = thickness / 2.0;
+                let corners = points.iter().flat_map(|p| {
+                    vec![[p[0] - t, p[1] - t], [p[0] + t, p[1] - t],
+                         [p[0] - t, p[1] + t], [p[0] + t, p[1] + t]]
+                });
+                points_bounding_box(matrix, corners)
+            },
 
-        let bot_right = *matrix * [1.0, -1.0, 1.0];
-        let bot_right = [bot_right[0] / bot_right[2], bot_right[1] / bot_right[2]];
+            &Shape::Polygon { ref matrix, ref points, .. } => {
+                points_bounding_box(matrix, points.iter().cloned())
+            },
 
-        (
-            max!(top_left[1], top_right[1], bot_left[1], bot_right[1]),
-            max!(top_left[0], top_right[0], bot_left[0], bot_right[0]),
-            min!(top_left[1], top_right[1], bot_left[1], bot_right[1]),
-            min!(top_left[0], top_right[0], bot_left[0], bot_right[0]),
-        )
+            _ => rect_bounding_box(self.matrix()),
+        }
     }
 
     /// Returns true if the point's coordinates hit the shape.
@@ -136,6 +244,196 @@ impl Shape {
             &Shape::Image { ref matrix, .. } => {
                 test(matrix, point)
             },
+
+            &Shape::Rectangle { ref matrix, .. } => {
+                test(matrix, point)
+            },
+
+            &Shape::RoundedRectangle { ref matrix, radius, .. } => {
+                let point = match local_point(matrix, point) { Some(p) => p, None => return false };
+                let (x, y) = (point[0].abs(), point[1].abs());
+
+                if x > 1.0 || y > 1.0 {
+                    return false;
+                }
+
+                // only the corners need a special treatment
+                let inner = 1.0 - radius;
+                if x <= inner || y <= inner {
+                    return true;
+                }
+
+                (x - inner) * (x - inner) + (y - inner) * (y - inner) <= radius * radius
+            },
+
+            &Shape::Ellipse { ref matrix, .. } => {
+                let point = match local_point(matrix, point) { Some(p) => p, None => return false };
+                point[0] * point[0] + point[1] * point[1] <= 1.0
+            },
+
+            &Shape::Line { ref matrix, ref points, thickness, .. } => {
+                let point = match local_point(matrix, point) { Some(p) => p, None => return false };
+                let half = thickness / 2.0;
+
+                if points.len() == 1 {
+                    return distance_to_segment(&point, &points[0], &points[0]) <= half;
+                }
+
+                points.windows(2).any(|s| distance_to_segment(&point, &s[0], &s[1]) <= half)
+            },
+
+            &Shape::Polygon { ref matrix, ref points, .. } => {
+                let point = match local_point(matrix, point) { Some(p) => p, None => return false };
+
+                // even-odd rule: we count the number of edges crossed by a ray going from the
+                // point towards the right
+                let mut inside = false;
+                for (n, a) in points.iter().enumerate() {
+                    let b = &points[(n + 1) % points.len()];
+
+                    if (a[1] > point[1]) != (b[1] > point[1]) {
+                        let cross_x = a[0] + (point[1] - a[1]) * (b[0] - a[0]) / (b[1] - a[1]);
+                        if point[0] < cross_x {
+                            inside = !inside;
+                        }
+                    }
+                }
+
+                inside
+            },
         }
     }
 }
+
+/// Region of a texture, in texture coordinates. The bottom-left corner of a texture is
+/// `(0.0, 0.0)` and its top-right corner is `(1.0, 1.0)`.
+///
+/// If the left is greater than the right or the bottom greater than the top, the texture is
+/// mirrored.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UvRect {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl UvRect {
+    /// Returns the region that covers the whole texture.
+    #[inline]
+    pub fn full() -> UvRect {
+        UvRect { top: 1.0, right: 1.0, bottom: 0.0, left: 0.0 }
+    }
+
+    /// Converts a region relative to this one into a region of the texture.
+    ///
+    /// For example if `self` is the left half of the texture, then `UvRect::full()` gives the
+    /// left half, and the right half of `UvRect::full()` gives the second quarter.
+    #[inline]
+    pub fn sub_region(&self, inner: &UvRect) -> UvRect {
+        let width = self.right - self.left;
+        let height = self.top - self.bottom;
+
+        UvRect {
+            top: self.bottom + inner.top * height,
+            right: self.left + inner.right * width,
+            bottom: self.bottom + inner.bottom * height,
+            left: self.left + inner.left * width,
+        }
+    }
+}
+
+impl Default for UvRect {
+    #[inline]
+    fn default() -> UvRect {
+        UvRect::full()
+    }
+}
+
+/// Converts a point in viewport coordinates into the coordinates of a shape.
+fn local_point(matrix: &Matrix, point: &[f32; 2]) -> Option<[f32; 2]> {
+    let inverse = matrix.inverse()?;
+    let local = inverse * [point[0], point[1], 1.0];
+    Some([local[0] / local[2], local[1] / local[2]])
+}
+
+/// Returns the distance between a point and the segment between `a` and `b`.
+fn distance_to_segment(point: &[f32; 2], a: &[f32; 2], b: &[f32; 2]) -> f32 {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let ap = [point[0] - a[0], point[1] - a[1]];
+    let len_sq = ab[0] * ab[0] + ab[1] * ab[1];
+
+    // position of the projection of the point on the segment, between 0 and 1
+    let t = if len_sq == 0.0 {
+        0.0
+    } else {
+        ((ap[0] * ab[0] + ap[1] * ab[1]) / len_sq).clamp(0.0, 1.0)
+    };
+
+    let dx = ap[0] - t * ab[0];
+    let dy = ap[1] - t * ab[1];
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// Returns the bounding box of a list of points multiplied by a matrix.
+///
+/// Order: top, right, bottom, left.
+fn points_bounding_box<I>(matrix: &Matrix, points: I) -> (f32, f32, f32, f32)
+                          where I: Iterator<Item = [f32; 2]>
+{
+    let mut points = points.peekable();
+
+    // an empty list of points is a degenerate box at the origin of the shape
+    if points.peek().is_none() {
+        let origin = *matrix * [0.0, 0.0, 1.0];
+        let origin = [origin[0] / origin[2], origin[1] / origin[2]];
+        return (origin[1], origin[0], origin[1], origin[0]);
+    }
+
+    points.fold((f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY),
+                |(top, right, bottom, left), p| {
+        let p = *matrix * [p[0], p[1], 1.0];
+        let p = [p[0] / p[2], p[1] / p[2]];
+        (top.max(p[1]), right.max(p[0]), bottom.min(p[1]), left.min(p[0]))
+    })
+}
+
+/// Returns the bounding box of the square from `-1.0` to `1.0` multiplied by a matrix.
+///
+/// Order: top, right, bottom, left.
+pub fn rect_bounding_box(matrix: &Matrix) -> (f32, f32, f32, f32) {
+    macro_rules! min {
+        ($f:expr, $($o:expr),+) => ({
+            let other = min!($($o),+);
+            if $f < other { $f } else { other }
+        });
+        ($f:expr) => ($f);
+    }
+
+    macro_rules! max {
+        ($f:expr, $($o:expr),+) => ({
+            let other = max!($($o),+);
+            if $f > other { $f } else { other }
+        });
+        ($f:expr) => ($f);
+    }
+
+    let top_left = *matrix * [-1.0, 1.0, 1.0];
+    let top_left = [top_left[0] / top_left[2], top_left[1] / top_left[2]];
+
+    let top_right = *matrix * [1.0, 1.0, 1.0];
+    let top_right = [top_right[0] / top_right[2], top_right[1] / top_right[2]];
+
+    let bot_left = *matrix * [-1.0, -1.0, 1.0];
+    let bot_left = [bot_left[0] / bot_left[2], bot_left[1] / bot_left[2]];
+
+    let bot_right = *matrix * [1.0, -1.0, 1.0];
+    let bot_right = [bot_right[0] / bot_right[2], bot_right[1] / bot_right[2]];
+
+    (
+        max!(top_left[1], top_right[1], bot_left[1], bot_right[1]),
+        max!(top_left[0], top_right[0], bot_left[0], bot_right[0]),
+        min!(top_left[1], top_right[1], bot_left[1], bot_right[1]),
+        min!(top_left[0], top_right[0], bot_left[0], bot_right[0]),
+    )
+}
@@ -0,0 +1,545 @@
+//! Measuring and laying out text.
+
+use Alignment;
+use HorizontalAlignment;
+use Matrix;
+use Shape;
+use UvRect;
+use VerticalAlignment;
+
+/// Style of a text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    /// Name of the font. An empty string designates the default font of the renderer.
+    pub font: String,
+    /// Height of a line relative to the height of the widget, for example `0.1` for ten lines.
+    /// If `None`, the text is as large as possible while fitting in the widget.
+    ///
+    /// This is used by the widgets to compute the matrix of their shapes and is ignored when
+    /// drawing.
+    pub size: Option<f32>,
+    /// RGBA colour of the text.
+    pub color: [f32; 4],
+    pub bold: bool,
+    pub italic: bool,
+    /// Outline drawn around the characters, if any.
+    pub outline: Option<Outline>,
+}
+
+impl Default for TextStyle {
+    #[inline]
+    fn default() -> TextStyle {
+        TextStyle {
+            font: String::new(),
+            size: None,
+            color: [1.0, 1.0, 1.0, 1.0],
+            bold: false,
+            italic: false,
+            outline: None,
+        }
+    }
+}
+
+/// Outline around the characters of a text.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outline {
+    /// RGBA colour of the outline.
+    pub color: [f32; 4],
+    /// Thickness of the outline, relative to the height of a line.
+    pub thickness: f32,
+}
+
+/// What to do with text that doesn't fit in its widget.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Overflow {
+    /// The text is drawn outside of the widget.
+    Visible,
+    /// The parts of the text that are outside of the widget are neither drawn nor hit-tested.
+    Clip,
+    /// The lines that are too long and the last line that fits are truncated and end with an
+    /// ellipsis. The lines that don't fit are removed.
+    Ellipsis,
+    /// The text is made smaller until it fits, but the height of a line can't be smaller
+    /// than `min_size` (with the same meaning as the `size` of a `TextStyle`). If the text still
+    /// doesn't fit, it is truncated like with `Ellipsis`.
+    ShrinkToFit {
+        min_size: f32,
+    },
+}
+
+impl Default for Overflow {
+    #[inline]
+    fn default() -> Overflow {
+        Overflow::Visible
+    }
+}
+
+/// Measures text. Passed to the `Ui` with `set_font_metrics` and given to the widgets when
+/// their layout is built.
+///
+/// All the values are in the same unit, which is arbitrary. Only the ratio between the widths
+/// and the line height matters.
+pub trait FontMetrics: Send + Sync {
+    /// Returns the height of a line of text.
+    fn line_height(&self, style: &TextStyle) -> f32;
+
+    /// Returns the horizontal distance between the start of a character and the start of the
+    /// next one.
+    fn advance(&self, c: char, style: &TextStyle) -> f32;
+
+    /// Returns the adjustment to add to the advance of `previous` when it is followed by `next`.
+    ///
+    /// The default implementation returns `0.0`.
+    #[inline]
+    fn kerning(&self, _previous: char, _next: char, _style: &TextStyle) -> f32 {
+        0.0
+    }
+
+    /// Returns the width of a line of text.
+    ///
+    /// The default implementation adds the advances and kernings of the characters.
+    fn text_width(&self, text: &str, style: &TextStyle) -> f32 {
+        let mut width = 0.0;
+        let mut previous = None;
+
+        for c in text.chars() {
+            if let Some(p) = previous {
+                width += self.kerning(p, c, style);
+            }
+
+            width += self.advance(c, style);
+            previous = Some(c);
+        }
+
+        width
+    }
+}
+
+/// Metrics of a font where all the characters have the same advance, whatever the style. Used
+/// by the `Ui` when no other metrics have been set.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MonospaceFontMetrics {
+    /// Advance of each character, relative to the line height.
+    pub advance: f32,
+}
+
+impl Default for MonospaceFontMetrics {
+    #[inline]
+    fn default() -> MonospaceFontMetrics {
+        MonospaceFontMetrics { advance: 0.5 }
+    }
+}
+
+impl FontMetrics for MonospaceFontMetrics {
+    #[inline]
+    fn line_height(&self, _: &TextStyle) -> f32 {
+        1.0
+    }
+
+    #[inline]
+    fn advance(&self, _: char, _: &TextStyle) -> f32 {
+        self.advance
+    }
+}
+
+/// A line of text produced by `wrap_text`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLine {
+    /// Content of the line, without the newline or the spaces where the line was broken.
+    pub text: String,
+    /// Position of the first byte of the line in the original text.
+    pub start: usize,
+    /// Width of the line according to the font metrics.
+    pub width: f32,
+}
+
+/// Splits a text in lines. A new line is started after each `\n`, and before each word that
+/// would make the line wider than `max_width`. Words that are wider than `max_width` are split.
+///
+/// If `max_width` is `None`, only the `\n` characters start new lines.
+pub fn wrap_text(text: &str, max_width: Option<f32>, style: &TextStyle, metrics: &FontMetrics)
+                 -> Vec<TextLine>
+{
+    wrap_with(text, max_width, |start, end| metrics.text_width(&text[start .. end], style))
+}
+
+/// Same as `wrap_text`, but `width` returns the width of the text between two byte positions.
+fn wrap_with<F>(text: &str, max_width: Option<f32>, width: F) -> Vec<TextLine>
+                where F: Fn(usize, usize) -> f32
+{
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+
+    for paragraph in text.split('\n') {
+        let paragraph_end = paragraph_start + paragraph.len();
+
+        match max_width {
+            Some(max_width) => wrap_paragraph(text, paragraph_start, paragraph_end, max_width,
+                                              &width, &mut lines),
+            None => lines.push(TextLine {
+                text: paragraph.to_owned(),
+                start: paragraph_start,
+                width: width(paragraph_start, paragraph_end),
+            }),
+        }
+
+        paragraph_start = paragraph_end + 1;
+    }
+
+    lines
+}
+
+/// Splits the paragraph of `text` between `start` and `end`, which doesn't contain any `\n`,
+/// and appends the lines to `out`.
+fn wrap_paragraph<F>(text: &str, start: usize, end: usize, max_width: f32, width: &F,
+                     out: &mut Vec<TextLine>)
+                     where F: Fn(usize, usize) -> f32
+{
+    let line = |line_start: usize, line_end: usize| {
+        TextLine {
+            text: text[line_start .. line_end].to_owned(),
+            start: line_start,
+            width: width(line_start, line_end),
+        }
+    };
+
+    // start of the current line and position of the end of its last word
+    let mut line_start = start;
+    let mut line_end = start;
+    let mut empty = true;
+
+    for (word_start, word) in words(&text[start .. end]) {
+        let word_start = start + word_start;
+        let word_end = word_start + word.len();
+
+        if !empty && width(line_start, word_end) <= max_width {
+            line_end = word_end;
+            continue;
+        }
+
+        if !empty {
+            out.push(line(line_start, line_end));
+        }
+
+        // the word starts a new line, and is split if it is too wide
+        line_start = word_start;
+        for (pos, c) in word.char_indices() {
+            let pos = word_start + pos;
+            if pos != line_start && width(line_start, pos + c.len_utf8()) > max_width {
+                out.push(line(line_start, pos));
+                line_start = pos;
+            }
+        }
+
+        line_end = word_end;
+        empty = false;
+    }
+
+    out.push(line(line_start, line_end));
+}
+
+/// Returns the words of a text and their position. Words are separated by whitespace.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut result = Vec::new();
+    let mut start = None;
+
+    for (pos, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => { result.push((s, &text[s .. pos])); start = None; },
+            (false, None) => start = Some(pos),
+            _ => ()
+        }
+    }
+
+    if let Some(s) = start {
+        result.push((s, &text[s ..]));
+    }
+
+    result
+}
+
+/// Converts the `size` of a `TextStyle`, which is relative to the height of the widget, into
+/// the height of a line in the coordinates of the widget. These coordinates go from `-1.0` to
+/// `1.0`, so the widget is `2.0` high and `2.0` wide.
+#[inline]
+pub fn size_to_line_height(size: f32) -> f32 {
+    size * 2.0
+}
+
+/// Returns the maximum width of a line, in the unit of the font metrics, for lines of text
+/// that fit in the width of a widget, which is `2.0` in its coordinates.
+///
+/// `line_height` is the height of a line in the coordinates of the widget, and
+/// `metrics_line_height` the height of a line according to the font metrics.
+#[inline]
+pub fn max_line_width(line_height: f32, metrics_line_height: f32, height_per_width: f32) -> f32 {
+    2.0 * metrics_line_height / (line_height * height_per_width)
+}
+
+/// Returns the largest height of a line, in the coordinates of the widget, so that a block of
+/// `num_lines` lines whose widest line is `widest` fits in the widget. Returns `None` if the
+/// lines are all empty.
+pub fn fitting_line_height(num_lines: usize, widest: f32, metrics_line_height: f32,
+                           height_per_width: f32) -> Option<f32>
+{
+    if num_lines == 0 || widest <= 0.0 {
+        return None;
+    }
+
+    // height per width of the text
+    let text_ratio = metrics_line_height * num_lines as f32 / widest;
+
+    Some(if height_per_width > text_ratio {
+        2.0 * text_ratio / (height_per_width * num_lines as f32)
+    } else {
+        2.0 / num_lines as f32
+    })
+}
+
+/// Keeps the first `max_lines` lines and truncates the lines that are wider than `max_width`.
+/// The truncated lines end with an ellipsis, and so does the last line if lines have been
+/// removed.
+pub fn truncate_lines(mut lines: Vec<TextLine>, max_lines: usize, max_width: f32,
+                      style: &TextStyle, metrics: &FontMetrics) -> Vec<TextLine>
+{
+    let removed = lines.len() > max_lines;
+    lines.truncate(max_lines);
+    let last = lines.len().wrapping_sub(1);
+
+    for (num, line) in lines.iter_mut().enumerate() {
+        if line.width <= max_width && !(removed && num == last) {
+            continue;
+        }
+
+        // removing characters until the text and the ellipsis fit
+        let mut text = line.text.clone();
+        loop {
+            let candidate = format!("{}\u{2026}", text.trim_end());
+            let width = metrics.text_width(&candidate, style);
+            if width <= max_width {
+                line.text = candidate;
+                line.width = width;
+                break;
+            }
+
+            if text.pop().is_none() {
+                line.text = String::new();
+                line.width = 0.0;
+                break;
+            }
+        }
+    }
+
+    lines
+}
+
+/// Builds one `Shape::Text` per line, for a widget whose height per width is
+/// `height_per_width`.
+///
+/// `line_height` is the height of a line in the coordinates of the widget, where the widget
+/// goes from `-1.0` to `1.0`. The lines are aligned horizontally according to
+/// `alignment.horizontal`, and the block of lines is aligned vertically according to
+/// `alignment.vertical`. Empty lines don't produce any shape but still take space.
+///
+/// The `size` of the style is ignored.
+pub fn layout_lines(lines: &[TextLine], line_height: f32, height_per_width: f32,
+                    alignment: Alignment, style: &TextStyle, metrics: &FontMetrics)
+                    -> Vec<Shape>
+{
+    let block_height = line_height * lines.len() as f32;
+    let top = match alignment.vertical {
+        VerticalAlignment::Center => block_height / 2.0,
+        VerticalAlignment::Top => 1.0,
+        VerticalAlignment::Bottom => -1.0 + block_height,
+    };
+
+    lines.iter().enumerate().filter(|&(_, line)| !line.text.is_empty()).map(|(num, line)| {
+        let half_height = line_height / 2.0;
+        let half_width = half_height * height_per_width * line.width / metrics.line_height(style);
+
+        let x = match alignment.horizontal {
+            HorizontalAlignment::Center => 0.0,
+            HorizontalAlignment::Left => -1.0 + half_width,
+            HorizontalAlignment::Right => 1.0 - half_width,
+        };
+
+        let y = top - line_height * num as f32 - half_height;
+
+        Shape::Text {
+            matrix: Matrix::translate(x, y) * Matrix::scale_wh(half_width, half_height),
+            text: line.text.clone(),
+            style: style.clone(),
+            tint: [1.0, 1.0, 1.0, 1.0],
+        }
+    }).collect()
+}
+
+/// Part of a rich text.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Span {
+    /// Text with a style. The `size` of the style is ignored.
+    Text {
+        text: String,
+        style: TextStyle,
+    },
+
+    /// An image drawn in the middle of the text.
+    Image {
+        name: String,
+        /// Width of the image, relative to the height of a line.
+        width: f32,
+    },
+}
+
+/// A line of rich text produced by `wrap_spans`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanLine {
+    /// The parts of the spans that are on this line.
+    pub pieces: Vec<SpanPiece>,
+    /// Width of the line according to the font metrics.
+    pub width: f32,
+}
+
+/// Part of a span that is on a line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanPiece {
+    /// Index of the span in the list passed to `wrap_spans`.
+    pub span: usize,
+    /// Part of the text of the span. Empty for images.
+    pub text: String,
+    /// Width of the piece according to the font metrics.
+    pub width: f32,
+}
+
+/// Character that represents an image when wrapping spans.
+const IMAGE_PLACEHOLDER: char = '\u{fffc}';
+
+/// Returns the height of a line of rich text according to the font metrics, which is the
+/// height of the tallest style.
+pub fn spans_line_height(spans: &[Span], metrics: &FontMetrics) -> f32 {
+    let height = spans.iter().fold(0.0, |height, span| {
+        match span {
+            &Span::Text { ref style, .. } => {
+                let h = metrics.line_height(style);
+                if h > height { h } else { height }
+            },
+            &Span::Image { .. } => height,
+        }
+    });
+
+    if height > 0.0 { height } else { metrics.line_height(&TextStyle::default()) }
+}
+
+/// Same as `wrap_text`, but for a list of spans that are laid out as a single flowing
+/// paragraph. Images are treated like characters.
+pub fn wrap_spans(spans: &[Span], max_width: Option<f32>, metrics: &FontMetrics)
+                  -> Vec<SpanLine>
+{
+    let line_height = spans_line_height(spans, metrics);
+
+    // the spans are concatenated in a single text, with a placeholder character for each image
+    let mut text = String::new();
+    let ranges: Vec<(usize, usize)> = spans.iter().map(|span| {
+        let start = text.len();
+        match span {
+            &Span::Text { text: ref t, .. } => text.push_str(t),
+            &Span::Image { .. } => text.push(IMAGE_PLACEHOLDER),
+        }
+        (start, text.len())
+    }).collect();
+
+    let pieces = |start: usize, end: usize| -> Vec<SpanPiece> {
+        spans.iter().zip(ranges.iter()).enumerate().filter_map(|(num, (span, &(s, e)))| {
+            let (s, e) = (if s > start { s } else { start }, if e < end { e } else { end });
+            if s >= e {
+                return None;
+            }
+
+            Some(match span {
+                &Span::Text { ref style, .. } => SpanPiece {
+                    span: num,
+                    text: text[s .. e].to_owned(),
+                    width: metrics.text_width(&text[s .. e], style),
+                },
+                &Span::Image { width, .. } => SpanPiece {
+                    span: num,
+                    text: String::new(),
+                    width: width * line_height,
+                },
+            })
+        }).collect()
+    };
+
+    let width = |start, end| pieces(start, end).iter().fold(0.0, |a, p| a + p.width);
+
+    wrap_with(&text, max_width, width).into_iter().map(|line| {
+        SpanLine {
+            pieces: pieces(line.start, line.start + line.text.len()),
+            width: line.width,
+        }
+    }).collect()
+}
+
+/// Same as `layout_lines`, but for lines produced by `wrap_spans`. Builds one `Shape::Text`
+/// per piece of text and one `Shape::Image` per image.
+///
+/// Each piece of text is as high as a line of its own style, and the pieces are aligned on the
+/// bottom of their line. Images are as high as the line, since their width is relative to it.
+pub fn layout_span_lines(spans: &[Span], lines: &[SpanLine], line_height: f32,
+                         height_per_width: f32, alignment: Alignment, metrics: &FontMetrics)
+                         -> Vec<Shape>
+{
+    // converts a width according to the font metrics into a width in the widget
+    let scale = line_height * height_per_width / spans_line_height(spans, metrics);
+
+    let block_height = line_height * lines.len() as f32;
+    let top = match alignment.vertical {
+        VerticalAlignment::Center => block_height / 2.0,
+        VerticalAlignment::Top => 1.0,
+        VerticalAlignment::Bottom => -1.0 + block_height,
+    };
+
+    let mut shapes = Vec::new();
+
+    for (num, line) in lines.iter().enumerate() {
+        let line_width = line.width * scale;
+        let mut x = match alignment.horizontal {
+            HorizontalAlignment::Center => -line_width / 2.0,
+            HorizontalAlignment::Left => -1.0,
+            HorizontalAlignment::Right => 1.0 - line_width,
+        };
+
+        let bottom = top - line_height * (num + 1) as f32;
+
+        for piece in &line.pieces {
+            let width = piece.width * scale;
+            let height = match spans[piece.span] {
+                Span::Text { ref style, .. } => {
+                    metrics.line_height(style) * scale / height_per_width
+                },
+                Span::Image { .. } => line_height,
+            };
+
+            let matrix = Matrix::translate(x + width / 2.0, bottom + height / 2.0) *
+                         Matrix::scale_wh(width / 2.0, height / 2.0);
+            x += width;
+
+            shapes.push(match spans[piece.span] {
+                Span::Text { ref style, .. } => Shape::Text {
+                    matrix: matrix,
+                    text: piece.text.clone(),
+                    style: style.clone(),
+                    tint: [1.0, 1.0, 1.0, 1.0],
+                },
+                Span::Image { ref name, .. } => Shape::Image {
+                    matrix: matrix,
+                    name: name.clone(),
+                    uv: UvRect::full(),
+                    tint: [1.0, 1.0, 1.0, 1.0],
+                },
+            });
+        }
+    }
+
+    shapes
+}
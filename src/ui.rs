@@ -1,17 +1,33 @@
 use std::any::Any;
+use std::cmp;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::mem;
+use std::ops::Deref;
 
 use predefined;
+use shape;
+use time;
 
 use Alignment;
+use Atlas;
 use Child;
+use Clipboard;
+use Drag;
+use FontMetrics;
+use Grid;
+use HitTestMode;
 use HorizontalAlignment;
+use Key;
 use Layout;
 use Matrix;
+use MemoryClipboard;
+use Modifiers;
+use MonospaceFontMetrics;
+use MouseButton;
 use Shape;
 use VerticalAlignment;
 use Widget;
@@ -21,8 +37,22 @@ pub struct Ui<S> {
     viewport_height_per_width: Mutex<f32>,
     widget: Arc<S>,
     main_node: Mutex<Node>,
+    draw_cache: Mutex<DrawCache>,
+    atlas: Mutex<Atlas>,
+    font_metrics: Mutex<Box<FontMetrics>>,
+    clipboard: Mutex<Box<Clipboard>>,
     hovering: AtomicBool,
-    mouse_down: AtomicBool,
+    cursor: Mutex<Option<[f32; 2]>>,
+    /// Last position of the cursor that wasn't `None`.
+    last_cursor: Mutex<[f32; 2]>,
+    buttons: Mutex<HashMap<MouseButton, ButtonState>>,
+    /// Widget that captured the pointer.
+    capture: Mutex<Option<Arc<Widget>>>,
+    double_click_interval_ns: Mutex<u64>,
+    drag: Mutex<DragState>,
+    drag_threshold: Mutex<f32>,
+    touches: Mutex<Touches>,
+    focus: Mutex<Option<Arc<Widget>>>,
 }
 
 impl<S> Ui<S> where S: Widget {
@@ -35,30 +65,36 @@ impl<S> Ui<S> where S: Widget {
             vertical: VerticalAlignment::Center,
         };
 
-        let main_node = Node::new(state.clone() as Arc<_>, viewport_height_per_width, alignment);
+        let font_metrics = MonospaceFontMetrics::default();
+        let main_node = Node::new(state.clone() as Arc<_>, viewport_height_per_width, alignment,
+                                  &font_metrics);
 
         Ui {
             viewport_height_per_width: Mutex::new(viewport_height_per_width),
             widget: state,
             main_node: Mutex::new(main_node),
+            draw_cache: Mutex::new(DrawCache { generation: 0, list: None, ghost_position: None }),
+            atlas: Mutex::new(Atlas::new()),
+            font_metrics: Mutex::new(Box::new(font_metrics)),
+            clipboard: Mutex::new(Box::new(MemoryClipboard::new())),
             hovering: AtomicBool::new(false),
-            mouse_down: AtomicBool::new(false),
+            cursor: Mutex::new(None),
+            last_cursor: Mutex::new([0.0, 0.0]),
+            buttons: Mutex::new(HashMap::new()),
+            capture: Mutex::new(None),
+            double_click_interval_ns: Mutex::new(500 * 1000000),     // 500ms
+            drag: Mutex::new(DragState::None),
+            drag_threshold: Mutex::new(0.02),
+            touches: Mutex::new(Touches { active: HashMap::new(), primary: None }),
+            focus: Mutex::new(None),
         }
     }
 
     /// Rebuilds the UI after the state has been changed.
     #[inline]
     pub fn rebuild(&self) {
-        let viewport: f32 = self.viewport_height_per_width.lock().unwrap().clone();
-
-        let alignment = Alignment {
-            horizontal: HorizontalAlignment::Center,
-            vertical: VerticalAlignment::Center,
-        };
-
-        *self.main_node.lock().unwrap() = Node::new(self.widget.clone(), viewport, alignment);
-
-        // TODO: update mouse?
+        let mut main_node = self.main_node.lock().unwrap();
+        self.rebuild_main_node(&mut main_node);
     }
 
     /// "Draws" the UI by returning a list of shapes. The list is ordered from bottom to top (in
@@ -68,22 +104,97 @@ impl<S> Ui<S> where S: Widget {
     /// The matrices stored in the shapes assume that the viewport uses OpenGL coordinates. This
     /// means that the viewport has a width of 2, a height of 2, and that the origin is at the
     /// center of the screen.
+    ///
+    /// During a drag and drop, the ghost shapes of the drag are at the end of the list.
+    ///
+    /// This function clones the shapes and doesn't return the clip rectangles. Use `draw_list`
+    /// to avoid that.
     #[inline]
     pub fn draw(&self) -> Vec<Shape> {
-        let viewport: f32 = self.viewport_height_per_width.lock().unwrap().clone();
+        self.draw_list().to_vec()
+    }
 
+    /// Same as `draw`, but returns a `DrawList` that shares the list of shapes with the `Ui`.
+    ///
+    /// The list is only regenerated when something has changed. When this is not the case,
+    /// the returned `DrawList` has the same generation as the previous one.
+    pub fn draw_list(&self) -> DrawList {
         let mut main_node = self.main_node.lock().unwrap();
+        let mut cache = self.draw_cache.lock().unwrap();
 
-        if main_node.needs_rebuild() {
-            let alignment = Alignment {
-                horizontal: HorizontalAlignment::Center,
-                vertical: VerticalAlignment::Center,
-            };
+        let updated = main_node.update(&**self.font_metrics.lock().unwrap());
+        if updated {
+            cache.list = None;
 
-            *main_node = Node::new(self.widget.clone(), viewport, alignment);
+            // the shapes may have moved under the cursor
+            let mut requests = Requests::default();
+            self.update_hover(&mut main_node, &mut requests);
+            self.apply_requests(&mut main_node, requests);
         }
 
-        main_node.build_shapes()
+        let drag = self.drag.lock().unwrap();
+
+        let ghost_position = match *drag {
+            DragState::Active { .. } => *self.cursor.lock().unwrap(),
+            _ => None,
+        };
+
+        if ghost_position != cache.ghost_position {
+            cache.list = None;
+            cache.ghost_position = ghost_position;
+        }
+
+        if let Some(ref list) = cache.list {
+            return list.clone();
+        }
+
+        let mut shapes = Vec::new();
+        let mut clip_rects = Vec::new();
+        main_node.build_shapes(&Matrix::identity(), &[1.0, 1.0, 1.0, 1.0], None, &mut shapes,
+                               &mut clip_rects);
+
+        if let (&DragState::Active { ref drag, .. }, Some(cursor)) = (&*drag, ghost_position) {
+            let matrix = Matrix::translate(cursor[0], cursor[1]);
+            for s in &drag.ghost {
+                shapes.push(s.clone().apply_matrix(&matrix));
+                clip_rects.push(None);
+            }
+        }
+
+        let shapes = {
+            let atlas = self.atlas.lock().unwrap();
+            shapes.into_iter().map(|s| atlas.resolve(s)).collect()
+        };
+
+        cache.generation += 1;
+        let list = DrawList {
+            generation: cache.generation,
+            shapes: Arc::new(shapes),
+            clip_rects: Arc::new(clip_rects),
+        };
+        cache.list = Some(list.clone());
+        list
+    }
+
+    /// Sets the atlas used to resolve the names of the images returned by `draw`.
+    pub fn set_atlas(&self, atlas: Atlas) {
+        *self.atlas.lock().unwrap() = atlas;
+        self.draw_cache.lock().unwrap().list = None;
+    }
+
+    /// Sets the metrics used by the widgets to measure text and rebuilds the UI.
+    ///
+    /// The default metrics are `MonospaceFontMetrics::default()`.
+    pub fn set_font_metrics<M>(&self, metrics: M) where M: FontMetrics + 'static {
+        *self.font_metrics.lock().unwrap() = Box::new(metrics);
+        self.rebuild();
+    }
+
+    /// Sets the clipboard used for copying and pasting text.
+    ///
+    /// The default clipboard is a `MemoryClipboard`.
+    pub fn set_clipboard<C>(&self, clipboard: C) where C: Clipboard + 'static {
+        *self.clipboard.lock().unwrap() = Box::new(clipboard);
     }
 
     /// Changes the height per width ratio of the viewport and rebuilds the UI.
@@ -104,19 +215,413 @@ impl<S> Ui<S> where S: Widget {
         }
     }
 
-    /// Sets the position and state of the cursor.
+    /// Sets the position of the cursor and the state of the left mouse button.
     ///
-    /// This function will search for shapes that collide with the cursor and send mouse events
-    /// to their owner.
+    /// This is a shortcut for `set_cursor_position` followed with `set_mouse_button`.
     pub fn set_cursor(&self, cursor: Option<[f32; 2]>, down: bool) {
+        self.set_cursor_position(cursor);
+        self.set_mouse_button(MouseButton::Left, down);
+    }
+
+    /// Sets the position of the cursor, or `None` if the cursor is outside of the viewport.
+    ///
+    /// This function will search for the topmost shape that collides with the cursor and send
+    /// mouse events to its owner.
+    ///
+    /// The widget under the cursor, or the widget that captured the pointer, receives a
+    /// `MouseMoveEvent`.
+    ///
+    /// If the cursor moves farther than the drag threshold while the left button is pressed,
+    /// the widget where the button was pressed receives a `DragStartEvent`.
+    pub fn set_cursor_position(&self, cursor: Option<[f32; 2]>) {
+        let mut main_node = self.main_node.lock().unwrap();
+        *self.cursor.lock().unwrap() = cursor;
+        if let Some(cursor) = cursor {
+            *self.last_cursor.lock().unwrap() = cursor;
+        }
+
+        let mut requests = Requests::default();
+        let hovered = self.update_hover(&mut main_node, &mut requests);
+
+        if let Some(cursor) = cursor {
+            let path = self.captured_path(&main_node).or(hovered.clone());
+            if let Some(path) = path {
+                let position = main_node.local_position(&path, &cursor);
+                let ev = predefined::MouseMoveEvent { position: position };
+                main_node.send_event_to_path(&path, Box::new(ev), &mut requests);
+            }
+        }
+
+        self.update_drag_position(&mut main_node, cursor, hovered.as_ref().map(|p| &p[..]),
+                                  &mut requests);
+        self.apply_requests(&mut main_node, requests);
+    }
+
+    /// Sets the state of a mouse button.
+    ///
+    /// Sends `MouseDownEvent` or `MouseUpEvent` to the widget under the cursor, or to the widget
+    /// that captured the pointer. If the button is released over the widget that was under the
+    /// cursor when it was pressed, this widget also receives a `MouseClick`, then a
+    /// `MouseDoubleClick` if it was already clicked in the double-click interval.
+    ///
+    /// The pointer capture ends when all the buttons are released.
+    pub fn set_mouse_button(&self, button: MouseButton, down: bool) {
+        let mut main_node = self.main_node.lock().unwrap();
+        let cursor = *self.cursor.lock().unwrap();
+
+        let target = cursor.and_then(|c| {
+            match main_node.hit_test(&c, &Matrix::identity()) {
+                Some(Hit::Node(path)) => Some((path, c)),
+                Some(Hit::Blocked) | None => None,
+            }
+        });
+
+        let mut requests = Requests::default();
+
+        {
+            let mut buttons = self.buttons.lock().unwrap();
+
+            {
+                let state = buttons.entry(button).or_default();
+                if state.down == down {
+                    return;
+                }
+                state.down = down;
+
+                let dropped = button == MouseButton::Left &&
+                              self.update_drag_button(&mut main_node, down, target.as_ref(),
+                                                      &mut requests);
+
+                if down {
+                    state.pressed = None;
+
+                    if let Some((ref path, ref cursor)) = target {
+                        state.pressed = Some(main_node.widget_at(path));
+                        let position = main_node.local_position(path, cursor);
+                        let ev = predefined::MouseDownEvent { button: button, position: position };
+                        main_node.send_event_to_path(path, Box::new(ev), &mut requests);
+                    }
+
+                } else if dropped {
+                    // the button has been released at the end of a drag and drop
+                    state.pressed = None;
+
+                } else {
+                    // the widget that captured the pointer receives the event even if the cursor
+                    // isn't over it
+                    let captured = self.captured_path(&main_node);
+                    let up_path = captured.or(target.as_ref().map(|t| t.0.clone()));
+                    let last_cursor = *self.last_cursor.lock().unwrap();
+
+                    if let Some(path) = up_path {
+                        let position = main_node.local_position(&path, &last_cursor);
+                        let ev = predefined::MouseUpEvent { button: button, position: position };
+                        main_node.send_event_to_path(&path, Box::new(ev), &mut requests);
+                    }
+
+                    let pressed = state.pressed.take();
+
+                    if let Some((ref path, ref cursor)) = target {
+                        let widget = main_node.widget_at(path);
+                        let clicked = match pressed {
+                            Some(ref pressed) => same_widget(pressed, &widget),
+                            None => false,
+                        };
+
+                        if clicked {
+                            let position = main_node.local_position(path, cursor);
+                            let ev = predefined::MouseClick { button: button, position: position };
+                            main_node.send_event_to_path(path, Box::new(ev), &mut requests);
+
+                            let now = time::precise_time_ns();
+                            let interval = *self.double_click_interval_ns.lock().unwrap();
+
+                            let double = match state.last_click {
+                                Some((ref w, t)) => same_widget(w, &widget) && now - t < interval,
+                                None => false,
+                            };
+
+                            if double {
+                                state.last_click = None;
+                                let ev = predefined::MouseDoubleClick { button: button,
+                                                                        position: position };
+                                main_node.send_event_to_path(path, Box::new(ev), &mut requests);
+                            } else {
+                                state.last_click = Some((widget, now));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if buttons.values().all(|b| !b.down) {
+                *self.capture.lock().unwrap() = None;
+            }
+        }
+
+        self.apply_requests(&mut main_node, requests);
+    }
+
+    /// Sends a `ScrollEvent` to the widget under the cursor.
+    ///
+    /// A positive `y` means scrolling towards the top of the content, and a positive `x` means
+    /// scrolling towards its right. One unit usually corresponds to one notch of the wheel.
+    pub fn scroll(&self, delta: [f32; 2]) {
+        let mut main_node = self.main_node.lock().unwrap();
+        let cursor = *self.cursor.lock().unwrap();
+
+        let path = match cursor.and_then(|c| main_node.hit_test(&c, &Matrix::identity())) {
+            Some(Hit::Node(path)) => path,
+            Some(Hit::Blocked) | None => return,
+        };
+
+        let mut requests = Requests::default();
+        let ev = Box::new(predefined::ScrollEvent { delta: delta }) as Box<Any>;
+        main_node.send_event_to_path(&path, ev, &mut requests);
+        self.apply_requests(&mut main_node, requests);
+    }
+
+    /// A finger has started touching the screen at the given position.
+    ///
+    /// Sends a `TouchStartEvent` to the widget under the finger. This widget will receive all the
+    /// other events of this touch, even if the finger moves away from it.
+    ///
+    /// If no other finger is touching the screen, this touch becomes the primary touch and also
+    /// moves the cursor and presses the left mouse button.
+    pub fn touch_start(&self, id: u64, position: [f32; 2]) {
+        let primary = {
+            let mut main_node = self.main_node.lock().unwrap();
+            let mut touches = self.touches.lock().unwrap();
+            let mut requests = Requests::default();
+
+            let target = match main_node.hit_test(&position, &Matrix::identity()) {
+                Some(Hit::Node(path)) => {
+                    let local = main_node.local_position(&path, &position);
+                    let ev = predefined::TouchStartEvent { id: id, position: local };
+                    main_node.send_event_to_path(&path, Box::new(ev), &mut requests);
+                    Some(main_node.widget_at(&path))
+                },
+                Some(Hit::Blocked) | None => None,
+            };
+
+            let primary = touches.active.is_empty();
+            if primary {
+                touches.primary = Some(id);
+            }
+
+            touches.active.insert(id, TouchState { target: target, position: position });
+
+            drop(touches);
+            self.apply_requests(&mut main_node, requests);
+            primary
+        };
+
+        if primary {
+            self.set_cursor_position(Some(position));
+            self.set_mouse_button(MouseButton::Left, true);
+        }
+    }
+
+    /// A finger that is touching the screen has moved.
+    ///
+    /// Sends a `TouchMoveEvent` to the widget where the touch started. If this is the primary
+    /// touch, also moves the cursor.
+    pub fn touch_move(&self, id: u64, position: [f32; 2]) {
+        let primary = {
+            let mut main_node = self.main_node.lock().unwrap();
+            let mut touches = self.touches.lock().unwrap();
+            let mut requests = Requests::default();
+
+            let target = match touches.active.get_mut(&id) {
+                Some(touch) => {
+                    touch.position = position;
+                    touch.target.clone()
+                },
+                None => return,
+            };
+
+            if let Some(path) = target.and_then(|t| main_node.find_widget(&t)) {
+                let local = main_node.local_position(&path, &position);
+                let ev = predefined::TouchMoveEvent { id: id, position: local };
+                main_node.send_event_to_path(&path, Box::new(ev), &mut requests);
+            }
+
+            let primary = touches.primary == Some(id);
+            drop(touches);
+            self.apply_requests(&mut main_node, requests);
+            primary
+        };
+
+        if primary {
+            self.set_cursor_position(Some(position));
+        }
+    }
+
+    /// A finger has stopped touching the screen.
+    ///
+    /// Sends a `TouchEndEvent` to the widget where the touch started. If this is the primary
+    /// touch, also releases the left mouse button and removes the cursor.
+    pub fn touch_end(&self, id: u64) {
+        let primary = {
+            let mut main_node = self.main_node.lock().unwrap();
+            let mut touches = self.touches.lock().unwrap();
+            let mut requests = Requests::default();
+
+            let touch = match touches.active.remove(&id) {
+                Some(t) => t,
+                None => return,
+            };
+
+            if let Some(path) = touch.target.and_then(|t| main_node.find_widget(&t)) {
+                let local = main_node.local_position(&path, &touch.position);
+                let ev = predefined::TouchEndEvent { id: id, position: local };
+                main_node.send_event_to_path(&path, Box::new(ev), &mut requests);
+            }
+
+            let primary = touches.primary == Some(id);
+            if primary {
+                touches.primary = None;
+            }
+
+            drop(touches);
+            self.apply_requests(&mut main_node, requests);
+            primary
+        };
+
+        if primary {
+            self.set_mouse_button(MouseButton::Left, false);
+            self.set_cursor_position(None);
+        }
+    }
+
+    /// Sets the distance, in viewport coordinates, that the cursor must travel while the left
+    /// button is pressed before a drag and drop starts. The default is `0.02`.
+    #[inline]
+    pub fn set_drag_threshold(&self, threshold: f32) {
+        *self.drag_threshold.lock().unwrap() = threshold;
+    }
+
+    /// Sets the maximal duration in nanoseconds between two clicks for them to be considered as
+    /// a double-click. The default is 500ms.
+    #[inline]
+    pub fn set_double_click_interval(&self, interval_ns: u64) {
+        *self.double_click_interval_ns.lock().unwrap() = interval_ns;
+    }
+
+    /// Sends a key press to the focused widget, or to the main widget if no widget has the focus.
+    ///
+    /// Pressing `Tab` then moves the focus to the next focusable widget, or to the previous one
+    /// if shift is pressed. Pressing `C`, `X` or `V` while ctrl is pressed calls `copy`, `cut`
+    /// or `paste`.
+    pub fn key_down(&self, key: Key, modifiers: Modifiers) {
+        self.send_to_focused(Box::new(predefined::KeyDownEvent { key: key, modifiers: modifiers }));
+
+        match key {
+            Key::Tab if modifiers.shift => self.focus_previous(),
+            Key::Tab => self.focus_next(),
+            Key::Char('c') | Key::Char('C') if modifiers.ctrl => self.copy(),
+            Key::Char('x') | Key::Char('X') if modifiers.ctrl => self.cut(),
+            Key::Char('v') | Key::Char('V') if modifiers.ctrl => self.paste(),
+            _ => (),
+        }
+    }
+
+    /// Sends a `CopyEvent` to the focused widget, or to the main widget if no widget has the
+    /// focus.
+    #[inline]
+    pub fn copy(&self) {
+        self.send_to_focused(Box::new(predefined::CopyEvent));
+    }
+
+    /// Sends a `CutEvent` to the focused widget, or to the main widget if no widget has the
+    /// focus.
+    #[inline]
+    pub fn cut(&self) {
+        self.send_to_focused(Box::new(predefined::CutEvent));
+    }
+
+    /// Sends the text of the clipboard to the focused widget, or to the main widget if no widget
+    /// has the focus. Does nothing if the clipboard doesn't contain any text.
+    pub fn paste(&self) {
+        let text = self.clipboard.lock().unwrap().get_text();
+        if let Some(text) = text {
+            self.send_to_focused(Box::new(predefined::PasteEvent { text: text }));
+        }
+    }
+
+    /// Sends a key release to the focused widget, or to the main widget if no widget has the
+    /// focus.
+    pub fn key_up(&self, key: Key, modifiers: Modifiers) {
+        self.send_to_focused(Box::new(predefined::KeyUpEvent { key: key, modifiers: modifiers }));
+    }
+
+    /// Sends a character to the focused widget, or to the main widget if no widget has the focus.
+    ///
+    /// This is the function to call when the user types text, as opposed to `key_down`.
+    pub fn character(&self, character: char) {
+        self.send_to_focused(Box::new(predefined::CharacterEvent(character)));
+    }
+
+    /// Sends the text being composed with an input method to the focused widget, or to the main
+    /// widget if no widget has the focus. An empty text cancels the composition.
+    ///
+    /// `cursor` is the position of the caret within `text` as a byte index, or `None` if the
+    /// caret should be hidden.
+    pub fn ime_preedit<T>(&self, text: T, cursor: Option<usize>) where T: Into<String> {
+        self.send_to_focused(Box::new(predefined::ImePreeditEvent {
+            text: text.into(),
+            cursor: cursor,
+        }));
+    }
+
+    /// Sends the text validated with an input method to the focused widget, or to the main
+    /// widget if no widget has the focus. This ends the composition.
+    pub fn ime_commit<T>(&self, text: T) where T: Into<String> {
+        self.send_to_focused(Box::new(predefined::ImeCommitEvent { text: text.into() }));
+    }
+
+    /// Returns the rectangle of the text caret of the focused widget in viewport coordinates,
+    /// or `None` if no widget has the focus or if the focused widget has no caret.
+    ///
+    /// This is where the candidate window of an input method should be placed. The rectangle
+    /// corresponds to the layout of the last call to `draw`.
+    pub fn caret_rect(&self) -> Option<ClipRect> {
+        let main_node = self.main_node.lock().unwrap();
+        let focus = self.focus.lock().unwrap();
+
+        let widget = focus.as_ref()?;
+        let path = main_node.find_widget(widget)?;
+        let matrix = main_node.matrix_of(&path);
+        widget.caret_rect().map(|rect| ClipRect::from_matrix(&(matrix * rect)))
+    }
+
+    /// Moves the focus to the next focusable widget, in the order in which they appear in the
+    /// hierarchy. If no widget has the focus, the first focusable widget receives it.
+    pub fn focus_next(&self) {
+        self.move_focus(false);
+    }
+
+    /// Moves the focus to the previous focusable widget, in the order in which they appear in the
+    /// hierarchy. If no widget has the focus, the last focusable widget receives it.
+    pub fn focus_previous(&self) {
+        self.move_focus(true);
+    }
+
+    /// Removes the focus from the widget that has it, if any.
+    pub fn clear_focus(&self) {
         let mut main_node = self.main_node.lock().unwrap();
-        main_node.mouse_update(cursor, &Matrix::identity(),
-                               self.mouse_down.swap(down, Ordering::Relaxed), down);
+        self.set_focus(&mut main_node, None);
+    }
 
-        // FIXME: update "hovering"
+    /// Returns true if one of the widgets of the UI has the focus.
+    pub fn has_focus(&self) -> bool {
+        self.focus.lock().unwrap().is_some()
     }
 
     /// Returns true if the mouse is hovering one of the elements of the UI.
+    ///
+    /// This is updated whenever the cursor moves or the UI is rebuilt, and can be used to
+    /// determine whether a click is destined to the UI or to what is behind it.
     pub fn is_hovering(&self) -> bool {
         self.hovering.load(Ordering::Relaxed)
     }
@@ -129,6 +634,400 @@ impl<S> Ui<S> where S: Widget {
     pub fn widget(&self) -> &S {
         &self.widget
     }
+
+    /// Builds a new main node to replace the existing one. The hover state of the nodes is kept,
+    /// then updated with the current position of the cursor since the shapes may have moved.
+    fn rebuild_main_node(&self, main_node: &mut Node) {
+        let viewport: f32 = self.viewport_height_per_width.lock().unwrap().clone();
+
+        let alignment = Alignment {
+            horizontal: HorizontalAlignment::Center,
+            vertical: VerticalAlignment::Center,
+        };
+
+        let mut new_node = Node::new(self.widget.clone(), viewport, alignment,
+                                     &**self.font_metrics.lock().unwrap());
+        new_node.inherit_state(main_node);
+        *main_node = new_node;
+        self.draw_cache.lock().unwrap().list = None;
+
+        let mut requests = Requests::default();
+        self.update_hover(main_node, &mut requests);
+        self.apply_requests(main_node, requests);
+    }
+
+    /// Determines the topmost node under the cursor, updates the hover state of the nodes and
+    /// `is_hovering`. Returns the path to the hovered node, if any.
+    fn update_hover(&self, main_node: &mut Node, requests: &mut Requests) -> Option<Vec<usize>> {
+        let cursor = *self.cursor.lock().unwrap();
+        let hit = cursor.and_then(|c| main_node.hit_test(&c, &Matrix::identity()));
+        self.hovering.store(hit.is_some(), Ordering::Relaxed);
+
+        let path = match hit {
+            Some(Hit::Node(path)) => Some(path),
+            Some(Hit::Blocked) | None => None,
+        };
+
+        main_node.update_hover(path.as_ref().map(|p| &p[..]), requests);
+        path
+    }
+
+    /// Updates the drag and drop state after the cursor moved, and sends `DragStartEvent` or
+    /// `DragMoveEvent` if necessary.
+    fn update_drag_position(&self, main_node: &mut Node, cursor: Option<[f32; 2]>,
+                            hovered: Option<&[usize]>, requests: &mut Requests)
+    {
+        let mut drag = self.drag.lock().unwrap();
+
+        let start = match *drag {
+            DragState::Pending { ref source, start } => {
+                let cursor = match cursor { Some(c) => c, None => return };
+                let threshold = *self.drag_threshold.lock().unwrap();
+                let (dx, dy) = (cursor[0] - start[0], cursor[1] - start[1]);
+                if dx * dx + dy * dy < threshold * threshold {
+                    return;
+                }
+
+                (source.clone(), start)
+            },
+
+            DragState::Active { .. } => {
+                if let (Some(path), Some(cursor)) = (hovered, cursor) {
+                    let position = main_node.local_position(path, &cursor);
+                    let ev = predefined::DragMoveEvent { position: position };
+                    main_node.send_event_to_path(path, Box::new(ev), requests);
+                }
+                return;
+            },
+
+            DragState::None => return,
+        };
+
+        // the cursor moved far enough, so we ask the source whether it wants to start a drag
+        *drag = DragState::None;
+
+        let (source, start) = start;
+        if let Some(path) = main_node.find_widget(&source) {
+            let position = main_node.local_position(&path, &start);
+            let ev = predefined::DragStartEvent { position: position };
+            requests.drag = None;
+            main_node.send_event_to_path(&path, Box::new(ev), requests);
+
+            if let Some(d) = requests.drag.take() {
+                *drag = DragState::Active { source: source, drag: d };
+            }
+        }
+    }
+
+    /// Updates the drag and drop state when the left mouse button is pressed or released.
+    ///
+    /// Returns true if the button has been released at the end of a drag and drop, in which case
+    /// `DropEvent` and `DragEndEvent` have been sent.
+    fn update_drag_button(&self, main_node: &mut Node, down: bool,
+                          target: Option<&(Vec<usize>, [f32; 2])>, requests: &mut Requests)
+                          -> bool
+    {
+        let mut drag = self.drag.lock().unwrap();
+
+        if down {
+            *drag = match target {
+                Some(&(ref path, cursor)) => {
+                    DragState::Pending { source: main_node.widget_at(path), start: cursor }
+                },
+                None => DragState::None,
+            };
+
+            return false;
+        }
+
+        let (source, payload) = match mem::replace(&mut *drag, DragState::None) {
+            DragState::Active { source, drag } => (source, drag.payload),
+            _ => return false,
+        };
+
+        if let Some(&(ref path, ref cursor)) = target {
+            let position = main_node.local_position(path, cursor);
+            let ev = predefined::DropEvent { position: position, payload: payload };
+            main_node.send_event_to_path(path, Box::new(ev), requests);
+        }
+
+        if let Some(path) = main_node.find_widget(&source) {
+            let ev = predefined::DragEndEvent { dropped: target.is_some() };
+            main_node.send_event_to_path(&path, Box::new(ev), requests);
+        }
+
+        true
+    }
+
+    /// Sends an event to the focused widget, or to the main widget if no widget has the focus.
+    fn send_to_focused(&self, event: Box<Any>) {
+        let mut main_node = self.main_node.lock().unwrap();
+
+        let path = {
+            let mut focus = self.focus.lock().unwrap();
+            let path = focus.as_ref().and_then(|w| main_node.find_widget(w));
+            if path.is_none() {
+                // the focused widget is no longer part of the hierarchy
+                *focus = None;
+            }
+            path.unwrap_or_default()
+        };
+
+        let mut requests = Requests::default();
+        main_node.send_event_to_path(&path, event, &mut requests);
+        self.apply_requests(&mut main_node, requests);
+    }
+
+    fn move_focus(&self, backward: bool) {
+        let mut main_node = self.main_node.lock().unwrap();
+
+        let mut focusable = Vec::new();
+        main_node.focusable_widgets(&mut focusable);
+        if focusable.is_empty() {
+            return;
+        }
+
+        let current = self.focus.lock().unwrap().as_ref()
+                          .and_then(|f| focusable.iter().position(|w| same_widget(w, f)));
+
+        let next = match (current, backward) {
+            (None, false) => 0,
+            (None, true) => focusable.len() - 1,
+            (Some(n), false) => (n + 1) % focusable.len(),
+            (Some(n), true) => (n + focusable.len() - 1) % focusable.len(),
+        };
+
+        let next = focusable[next].clone();
+        self.set_focus(&mut main_node, Some(next));
+    }
+
+    /// Changes the focused widget and sends `FocusLostEvent` and `FocusGainedEvent`.
+    ///
+    /// The requests made by widgets while handling these two events are applied once the focus
+    /// has changed.
+    fn set_focus(&self, main_node: &mut Node, widget: Option<Arc<Widget>>) {
+        let requests = {
+            let mut focus = self.focus.lock().unwrap();
+
+            match (focus.as_ref(), widget.as_ref()) {
+                (Some(old), Some(new)) if same_widget(old, new) => return,
+                (None, None) => return,
+                _ => ()
+            }
+
+            let mut requests = Requests::default();
+
+            if let Some(path) = focus.as_ref().and_then(|w| main_node.find_widget(w)) {
+                let ev = Box::new(predefined::FocusLostEvent) as Box<Any>;
+                main_node.send_event_to_path(&path, ev, &mut requests);
+            }
+
+            if let Some(path) = widget.as_ref().and_then(|w| main_node.find_widget(w)) {
+                let ev = Box::new(predefined::FocusGainedEvent) as Box<Any>;
+                main_node.send_event_to_path(&path, ev, &mut requests);
+            }
+
+            *focus = widget;
+            requests
+        };
+
+        self.apply_requests(main_node, requests);
+    }
+
+    /// Returns the path to the widget that captured the pointer, if any.
+    fn captured_path(&self, main_node: &Node) -> Option<Vec<usize>> {
+        let capture = self.capture.lock().unwrap();
+        capture.as_ref().and_then(|w| main_node.find_widget(w))
+    }
+
+    /// Handles the requests that widgets made while processing events.
+    fn apply_requests(&self, main_node: &mut Node, requests: Requests) {
+        if let Some(widget) = requests.capture {
+            // the capture only lasts while a button is pressed
+            if self.buttons.lock().unwrap().values().any(|b| b.down) {
+                *self.capture.lock().unwrap() = Some(widget);
+            }
+        }
+
+        if let Some(widget) = requests.focus {
+            if widget.is_focusable() {
+                self.set_focus(main_node, Some(widget));
+            }
+        }
+
+        if let Some(text) = requests.clipboard {
+            self.clipboard.lock().unwrap().set_text(text);
+        }
+    }
+}
+
+/// List of shapes returned by `Ui::draw_list`. Dereferences to a slice of shapes.
+///
+/// Cloning a `DrawList` is cheap. Two lists with the same generation contain the same shapes,
+/// which allows renderers to skip uploading their vertex data again when nothing has changed.
+///
+/// Shapes that belong to a clipped layout (for example `Layout::ScrollView`) have a clip
+/// rectangle. Only the parts of these shapes that are inside of the rectangle must be drawn,
+/// for example by using a scissor test.
+#[derive(Clone, Debug)]
+pub struct DrawList {
+    generation: u64,
+    shapes: Arc<Vec<Shape>>,
+    clip_rects: Arc<Vec<Option<ClipRect>>>,
+}
+
+impl DrawList {
+    /// Returns the generation of this list. It is incremented every time the `Ui` generates a
+    /// new list.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the clip rectangle of each shape, in the same order as the shapes.
+    #[inline]
+    pub fn clip_rects(&self) -> &[Option<ClipRect>] {
+        &self.clip_rects
+    }
+
+    /// Returns the clip rectangle of the shape at the given index, or `None` if it isn't
+    /// clipped.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the index is out of range.
+    #[inline]
+    pub fn clip_rect(&self, index: usize) -> Option<ClipRect> {
+        self.clip_rects[index]
+    }
+
+    /// Iterates over the shapes and their clip rectangle.
+    #[inline]
+    pub fn iter_with_clip_rects<'a>(&'a self) -> Box<Iterator<Item = (&'a Shape, Option<ClipRect>)> + 'a> {
+        Box::new(self.shapes.iter().zip(self.clip_rects.iter().cloned()))
+    }
+}
+
+/// Rectangle in viewport coordinates outside of which a shape must not be drawn.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClipRect {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl ClipRect {
+    /// Returns the bounding box of the square from `-1.0` to `1.0` multiplied by a matrix.
+    #[inline]
+    pub fn from_matrix(matrix: &Matrix) -> ClipRect {
+        let (top, right, bottom, left) = shape::rect_bounding_box(matrix);
+        ClipRect { top: top, right: right, bottom: bottom, left: left }
+    }
+
+    /// Returns the intersection of two rectangles.
+    #[inline]
+    pub fn intersection(&self, other: &ClipRect) -> ClipRect {
+        ClipRect {
+            top: self.top.min(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.max(other.bottom),
+            left: self.left.max(other.left),
+        }
+    }
+
+    /// Returns true if the point is inside of the rectangle.
+    #[inline]
+    pub fn contains(&self, point: &[f32; 2]) -> bool {
+        point[0] >= self.left && point[0] <= self.right &&
+        point[1] >= self.bottom && point[1] <= self.top
+    }
+}
+
+impl Deref for DrawList {
+    type Target = [Shape];
+
+    #[inline]
+    fn deref(&self) -> &[Shape] {
+        &self.shapes
+    }
+}
+
+/// The last list of shapes generated by `Ui::draw_list`.
+struct DrawCache {
+    generation: u64,
+    /// `None` if the list must be generated again.
+    list: Option<DrawList>,
+    /// Position of the ghost of the drag and drop in the list.
+    ghost_position: Option<[f32; 2]>,
+}
+
+/// State of a mouse button.
+#[derive(Default)]
+struct ButtonState {
+    down: bool,
+    /// Widget that was under the cursor when the button was pressed.
+    pressed: Option<Arc<Widget>>,
+    /// Widget that was last clicked with this button and the time of the click, used to detect
+    /// double-clicks.
+    last_click: Option<(Arc<Widget>, u64)>,
+}
+
+/// State of the drag and drop.
+enum DragState {
+    None,
+    /// The left button has been pressed over `source` at the position `start`, but the cursor
+    /// hasn't moved enough yet.
+    Pending {
+        source: Arc<Widget>,
+        start: [f32; 2],
+    },
+    /// A drag and drop started by `source` is in progress.
+    Active {
+        source: Arc<Widget>,
+        drag: Drag,
+    },
+}
+
+/// Touches in progress.
+struct Touches {
+    active: HashMap<u64, TouchState>,
+    /// Identifier of the touch that controls the cursor.
+    primary: Option<u64>,
+}
+
+struct TouchState {
+    /// Widget that was under the finger when the touch started.
+    target: Option<Arc<Widget>>,
+    /// Last known position of the finger.
+    position: [f32; 2],
+}
+
+/// Requests made by widgets through their `EventOutcome` that must be handled by the `Ui`.
+#[derive(Default)]
+struct Requests {
+    /// Widget that asked for the focus.
+    focus: Option<Arc<Widget>>,
+    /// Drag and drop that a widget wants to start.
+    drag: Option<Drag>,
+    /// Widget that wants to capture the pointer.
+    capture: Option<Arc<Widget>>,
+    /// Text to put in the clipboard.
+    clipboard: Option<String>,
+}
+
+/// Result of a hit test.
+enum Hit {
+    /// Path to the node that owns the topmost shape under the point.
+    Node(Vec<usize>),
+    /// The topmost shape under the point is an event blocker.
+    Blocked,
+}
+
+/// Returns true if both `Arc`s point to the same widget.
+#[inline]
+fn same_widget(a: &Arc<Widget>, b: &Arc<Widget>) -> bool {
+    &**a as *const Widget as *const () == &**b as *const Widget as *const ()
 }
 
 struct Node {
@@ -137,6 +1036,23 @@ struct Node {
     children: Vec<(Matrix, Node)>,
     shapes: Vec<Shape>,
     needs_rebuild: bool,
+    /// True if one of the shapes of this node is the topmost shape under the mouse cursor.
+    hovered: bool,
+    /// If true, the content of this node that is outside of its bounds is neither drawn nor
+    /// hit-tested.
+    clip: bool,
+    /// If true, the points inside the bounds of this node that don't hit any shape or child hit
+    /// the node itself.
+    hit_area: bool,
+    /// Colour that multiplies the colour of the shapes of this node and its children.
+    tint: [f32; 4],
+
+    // parameters that were passed to `build_layout`
+    height_per_width: f32,
+    alignment: Alignment,
+    /// True if the layout of this node depends on the empty space around its children, in which
+    /// case it must be rebuilt when they change.
+    depends_on_children: bool,
 
     // empty space around the widget in local coordinates
     empty_top: f32,
@@ -145,9 +1061,92 @@ struct Node {
     empty_left: f32,
 }
 
+/// Parameters used when building a node and the nodes of its children.
+struct BuildContext<'a> {
+    /// Height per width of the node, passed to `build_layout`.
+    height_per_width: f32,
+    /// Alignment of the node, passed to `build_layout`.
+    alignment: Alignment,
+    /// Nodes of the previous layout that can be reused for the children.
+    pool: Vec<Node>,
+    /// If true, the nodes taken from the pool haven't been updated yet and `update` is called on
+    /// them.
+    update_reused: bool,
+    metrics: &'a FontMetrics,
+}
+
+impl<'a> BuildContext<'a> {
+    /// Returns the context of a child of this node, whose children are taken from `pool`.
+    #[inline]
+    fn child(&self, height_per_width: f32, alignment: Alignment, pool: Vec<Node>)
+             -> BuildContext<'a>
+    {
+        BuildContext {
+            height_per_width: height_per_width,
+            alignment: alignment,
+            pool: pool,
+            update_reused: self.update_reused,
+            metrics: self.metrics,
+        }
+    }
+}
+
 impl Node {
-    fn new(state: Arc<Widget>, my_height_per_width: f32, alignment: Alignment) -> Node {
-        match state.build_layout(my_height_per_width, alignment) {
+    #[inline]
+    fn new(state: Arc<Widget>, my_height_per_width: f32, alignment: Alignment,
+           metrics: &FontMetrics) -> Node
+    {
+        Node::build(state, BuildContext {
+            height_per_width: my_height_per_width,
+            alignment: alignment,
+            pool: Vec::new(),
+            update_reused: false,
+            metrics: metrics,
+        })
+    }
+
+    /// Builds the node of a child of the node described by `parent`. If the pool of `parent`
+    /// contains a node of the same widget that was built with the same parameters, it is reused
+    /// instead of calling `build_layout`.
+    fn child(state: Arc<Widget>, my_height_per_width: f32, alignment: Alignment,
+             parent: &mut BuildContext) -> Node
+    {
+        let old = match parent.pool.iter().position(|n| same_widget(&n.state, &state)) {
+            Some(pos) => parent.pool.swap_remove(pos),
+            None => return Node::build(state, parent.child(my_height_per_width, alignment,
+                                                           Vec::new())),
+        };
+
+        if old.height_per_width == my_height_per_width && old.alignment == alignment {
+            let mut old = old;
+            if parent.update_reused {
+                old.update(parent.metrics);
+            }
+            return old;
+        }
+
+        let hovered = old.hovered;
+        let old_children = old.children.into_iter().map(|(_, n)| n).collect();
+        let mut node = Node::build(state, parent.child(my_height_per_width, alignment,
+                                                       old_children));
+        node.hovered = hovered;
+        node
+    }
+
+    /// Builds a node by calling `build_layout`. The children are taken from the pool of `ctxt`
+    /// if possible.
+    fn build(state: Arc<Widget>, ctxt: BuildContext) -> Node {
+        let layout = state.build_layout_with_metrics(ctxt.height_per_width, ctxt.alignment,
+                                                     ctxt.metrics);
+        Node::from_layout(state, layout, ctxt)
+    }
+
+    /// Builds a node from the layout returned by its widget.
+    fn from_layout(state: Arc<Widget>, layout: Layout, mut ctxt: BuildContext) -> Node {
+        let my_height_per_width = ctxt.height_per_width;
+        let alignment = ctxt.alignment;
+
+        match layout {
             Layout::AbsolutePositionned(list) => {
                 // TODO: arbitrary alignment
                 let children_alignment = Alignment {
@@ -170,7 +1169,7 @@ impl Node {
                     let width = (width[0] * width[0] + width[1] * width[1]).sqrt();
 
                     let child_width_per_height = my_height_per_width * height / width;
-                    (m, Node::new(w, child_width_per_height, children_alignment))
+                    (m, Node::child(w, child_width_per_height, children_alignment, &mut ctxt))
                 }).collect();
 
                 Node {
@@ -178,6 +1177,13 @@ impl Node {
                     children: new_children,
                     shapes: Vec::new(),
                     needs_rebuild: false,
+                    hovered: false,
+                    clip: false,
+                    hit_area: false,
+                    tint: [1.0, 1.0, 1.0, 1.0],
+                    height_per_width: my_height_per_width,
+                    alignment: alignment,
+                    depends_on_children: false,
                     empty_top: 0.0,
                     empty_right: 0.0,
                     empty_bottom: 0.0,
@@ -185,14 +1191,18 @@ impl Node {
                 }
             },
 
-            Layout::HorizontalBar { alignment, children, vertical_align } => {
-                Node::with_layout(state, children, Alignment { horizontal: alignment, .. Default::default() },
-                                  false, my_height_per_width, vertical_align)
+            Layout::HorizontalBar { alignment: flow_alignment, children, vertical_align } => {
+                Node::with_layout(state, children, Alignment { horizontal: flow_alignment, .. Default::default() },
+                                  false, vertical_align, ctxt)
             },
 
-            Layout::VerticalBar { alignment, children, horizontal_align } => {
-                Node::with_layout(state, children, Alignment { vertical: alignment, .. Default::default() },
-                                  true, my_height_per_width, horizontal_align)
+            Layout::VerticalBar { alignment: flow_alignment, children, horizontal_align } => {
+                Node::with_layout(state, children, Alignment { vertical: flow_alignment, .. Default::default() },
+                                  true, horizontal_align, ctxt)
+            },
+
+            Layout::Grid(grid) => {
+                Node::with_grid(state, grid, ctxt)
             },
 
             Layout::Shapes(shapes) => {
@@ -217,18 +1227,80 @@ impl Node {
                     children: Vec::new(),
                     shapes: shapes,
                     needs_rebuild: false,
+                    hovered: false,
+                    clip: false,
+                    hit_area: false,
+                    tint: [1.0, 1.0, 1.0, 1.0],
+                    height_per_width: my_height_per_width,
+                    alignment: alignment,
+                    depends_on_children: false,
                     empty_top: empty_top,
                     empty_right: empty_right,
                     empty_bottom: empty_bottom,
                     empty_left: empty_left,
                 }
-            }
+            },
+
+            Layout::ScrollView { child, content_size, scroll } => {
+                let scroll = [scroll[0].clamp(0.0, 1.0), scroll[1].clamp(0.0, 1.0)];
+
+                // position of the center of the content, so that a scroll of `0.0` aligns the
+                // content with the top-left corner and `1.0` with the bottom-right corner
+                let x = (content_size[0] - 1.0) * (1.0 - 2.0 * scroll[0]);
+                let y = (content_size[1] - 1.0) * (2.0 * scroll[1] - 1.0);
+                let matrix = Matrix::translate(x, y) *
+                             Matrix::scale_wh(content_size[0], content_size[1]);
+
+                let child_height_per_width = my_height_per_width * content_size[1] / content_size[0];
+                let child = Node::child(child, child_height_per_width, alignment, &mut ctxt);
+
+                Node {
+                    state: state,
+                    children: vec![(matrix, child)],
+                    shapes: Vec::new(),
+                    needs_rebuild: false,
+                    hovered: false,
+                    clip: true,
+                    hit_area: true,
+                    tint: [1.0, 1.0, 1.0, 1.0],
+                    height_per_width: my_height_per_width,
+                    alignment: alignment,
+                    depends_on_children: false,
+                    empty_top: 0.0,
+                    empty_right: 0.0,
+                    empty_bottom: 0.0,
+                    empty_left: 0.0,
+                }
+            },
+
+            Layout::Clipped(layout) => {
+                let mut node = Node::from_layout(state, *layout, ctxt);
+                node.clip = true;
+
+                // nothing is visible outside of the widget
+                node.empty_top = node.empty_top.max(0.0);
+                node.empty_right = node.empty_right.max(0.0);
+                node.empty_bottom = node.empty_bottom.max(0.0);
+                node.empty_left = node.empty_left.max(0.0);
+                node
+            },
+
+            Layout::Tinted { tint, layout } => {
+                let mut node = Node::from_layout(state, *layout, ctxt);
+                for (t, v) in node.tint.iter_mut().zip(tint.iter()) {
+                    *t *= *v;
+                }
+                node
+            },
         }
     }
 
     fn with_layout(state: Arc<Widget>, children: Vec<Child>, alignment: Alignment, vertical: bool,
-                   my_height_per_width: f32, other_align: bool) -> Node
+                   other_align: bool, mut ctxt: BuildContext) -> Node
     {
+        let my_height_per_width = ctxt.height_per_width;
+        let my_alignment = ctxt.alignment;
+
         // In this function, the word "flow" designates the dimension that is being operated and
         // "perpendicular" designates the other dimension. If `vertical` is true, then the flow
         // is the y dimension and the perpendicular dimension is x.
@@ -246,7 +1318,8 @@ impl Node {
             };
 
             // building its node
-            let node = Node::new(child.child.clone(), height_per_width, child.alignment);
+            let node = Node::child(child.child.clone(), height_per_width, child.alignment,
+                                   &mut ctxt);
             (child, node)
         }).collect();
 
@@ -440,6 +1513,13 @@ impl Node {
             children: children,
             shapes: Vec::new(),
             needs_rebuild: false,
+            hovered: false,
+            clip: false,
+            hit_area: false,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            height_per_width: my_height_per_width,
+            alignment: my_alignment,
+            depends_on_children: true,
             empty_top: my_empty_top,
             empty_right: my_empty_right,
             empty_bottom: my_empty_bottom,
@@ -447,48 +1527,286 @@ impl Node {
         }
     }
 
-    #[inline]
-    fn needs_rebuild(&mut self) -> bool {
-        if self.needs_rebuild {
-            self.needs_rebuild = false;
-            return true;
+    fn with_grid(state: Arc<Widget>, grid: Grid, mut ctxt: BuildContext) -> Node {
+        let my_height_per_width = ctxt.height_per_width;
+        let my_alignment = ctxt.alignment;
+
+        let Grid { columns, rows, children } = grid;
+
+        // position of the borders of the columns from left to right, or of the rows from top
+        // to bottom
+        let borders = |weights: &[i8], start: f32, direction: f32| {
+            let sum = weights.iter().fold(0, |a, &w| a + w as i32);
+
+            // if the weights don't add up to a positive number, all the cells have the same size
+            let weight = |w: i8| if sum > 0 { w as i32 } else { 1 };
+            let sum = if sum > 0 { sum } else { weights.len() as i32 };
+
+            let mut current = 0;
+            let mut out = vec![start];
+            for &w in weights {
+                current += weight(w);
+                out.push(start + direction * 2.0 * current as f32 / sum as f32);
+            }
+            out
+        };
+
+        let column_borders = borders(&columns, -1.0, 1.0);
+        let row_borders = borders(&rows, 1.0, -1.0);
+
+        let mut my_empty_top = 1.0;
+        let mut my_empty_right = 1.0;
+        let mut my_empty_bottom = 1.0;
+        let mut my_empty_left = 1.0;
+
+        let children = children.into_iter().filter_map(|child| {
+            let column_span = cmp::max(child.column_span, 1);
+            let row_span = cmp::max(child.row_span, 1);
+            if child.column + column_span > columns.len() || child.row + row_span > rows.len() {
+                return None;
+            }
+
+            let left = column_borders[child.column];
+            let right = column_borders[child.column + column_span];
+            let top = row_borders[child.row];
+            let bottom = row_borders[child.row + row_span];
+            let (half_width, half_height) = ((right - left) * 0.5, (top - bottom) * 0.5);
+
+            let height_per_width = my_height_per_width * half_height / half_width;
+            let node = Node::child(child.child, height_per_width, child.alignment, &mut ctxt);
+
+            // moving the content of the child towards the side it is aligned to
+            let x = match child.alignment.horizontal {
+                HorizontalAlignment::Left => -node.empty_left,
+                HorizontalAlignment::Center => (node.empty_right - node.empty_left) * 0.5,
+                HorizontalAlignment::Right => node.empty_right,
+            };
+            let y = match child.alignment.vertical {
+                VerticalAlignment::Top => node.empty_top,
+                VerticalAlignment::Center => (node.empty_top - node.empty_bottom) * 0.5,
+                VerticalAlignment::Bottom => -node.empty_bottom,
+            };
+
+            // adjusting the `my_empty_*` variables
+            let child_empty_top = 1.0 - top + half_height * (node.empty_top - y);
+            if child_empty_top < my_empty_top { my_empty_top = child_empty_top }
+            let child_empty_right = 1.0 - right + half_width * (node.empty_right - x);
+            if child_empty_right < my_empty_right { my_empty_right = child_empty_right }
+            let child_empty_bottom = 1.0 + bottom + half_height * (node.empty_bottom + y);
+            if child_empty_bottom < my_empty_bottom { my_empty_bottom = child_empty_bottom }
+            let child_empty_left = 1.0 + left + half_width * (node.empty_left + x);
+            if child_empty_left < my_empty_left { my_empty_left = child_empty_left }
+
+            let matrix = Matrix::translate((left + right) * 0.5, (top + bottom) * 0.5) *
+                         Matrix::scale_wh(half_width, half_height) * Matrix::translate(x, y);
+            Some((matrix, node))
+        }).collect();
+
+        Node {
+            state: state,
+            children: children,
+            shapes: Vec::new(),
+            needs_rebuild: false,
+            hovered: false,
+            clip: false,
+            hit_area: false,
+            tint: [1.0, 1.0, 1.0, 1.0],
+            height_per_width: my_height_per_width,
+            alignment: my_alignment,
+            depends_on_children: true,
+            empty_top: my_empty_top,
+            empty_right: my_empty_right,
+            empty_bottom: my_empty_bottom,
+            empty_left: my_empty_left,
         }
+    }
 
-        if self.state.needs_rebuild() {
+    /// Rebuilds the nodes of this hierarchy whose widget needs it, and their parents if the space
+    /// taken by their content has changed. Returns true if anything has been rebuilt.
+    fn update(&mut self, metrics: &FontMetrics) -> bool {
+        let dirty = mem::replace(&mut self.needs_rebuild, false);
+        if self.state.needs_rebuild() || dirty {
+            self.rebuild(true, metrics);
             return true;
         }
 
+        let mut changed = false;
+        let mut children_empty_changed = false;
+
         for &mut (_, ref mut child) in &mut self.children {
-            if child.needs_rebuild() {
-                return true;
+            let empty = child.empty_space();
+            if child.update(metrics) {
+                changed = true;
+                if child.empty_space() != empty {
+                    children_empty_changed = true;
+                }
             }
         }
 
-        false
+        if children_empty_changed && self.depends_on_children {
+            self.rebuild(false, metrics);
+        }
+
+        changed
     }
 
-    fn build_shapes(&self) -> Vec<Shape> {
-        let mut result = Vec::new();
+    /// Calls `build_layout` again on this node's widget, reusing the existing children nodes
+    /// when possible. `update_children` has the same meaning as the `update_reused` field of
+    /// `BuildContext`.
+    fn rebuild(&mut self, update_children: bool, metrics: &FontMetrics) {
+        let pool = mem::take(&mut self.children).into_iter().map(|(_, n)| n).collect();
+        let mut node = Node::build(self.state.clone(), BuildContext {
+            height_per_width: self.height_per_width,
+            alignment: self.alignment,
+            pool: pool,
+            update_reused: update_children,
+            metrics: metrics,
+        });
+        node.hovered = self.hovered;
+        *self = node;
+    }
+
+    /// Returns the empty space around the content. Order: top, right, bottom, left.
+    #[inline]
+    fn empty_space(&self) -> (f32, f32, f32, f32) {
+        (self.empty_top, self.empty_right, self.empty_bottom, self.empty_left)
+    }
+
+    /// Appends the shapes of this node and its children to `out`, and their clip rectangle to
+    /// `clip_rects`. The `matrix` is the absolute matrix of this node, `tint` is the tint of its
+    /// parent and `clip` is the clip rectangle of its parent.
+    fn build_shapes(&self, matrix: &Matrix, tint: &[f32; 4], clip: Option<ClipRect>,
+                    out: &mut Vec<Shape>, clip_rects: &mut Vec<Option<ClipRect>>)
+    {
+        let tint = [tint[0] * self.tint[0], tint[1] * self.tint[1], tint[2] * self.tint[2],
+                    tint[3] * self.tint[3]];
+
+        let clip = if self.clip {
+            let rect = ClipRect::from_matrix(matrix);
+            Some(clip.map_or(rect, |c| c.intersection(&rect)))
+        } else {
+            clip
+        };
 
         for &(ref m, ref c) in &self.children {
-            for s in c.build_shapes() { result.push(s.apply_matrix(m)); }
+            c.build_shapes(&(*matrix * *m), &tint, clip, out, clip_rects);
         }
 
         for s in &self.shapes {
-            result.push(s.clone());
+            let s = s.clone().apply_matrix(matrix).apply_tint(&tint);
+
+            // shapes that are entirely outside of the clip rectangle are skipped
+            if let Some(ref clip) = clip {
+                let (t, r, b, l) = s.get_bounding_box();
+                if t <= clip.bottom || r <= clip.left || b >= clip.top || l >= clip.right {
+                    continue;
+                }
+            }
+
+            out.push(s);
+            clip_rects.push(clip);
         }
+    }
 
-        result
+    /// Copies the state of `old`, which is the node that this one replaces, to this node and its
+    /// children.
+    fn inherit_state(&mut self, old: &Node) {
+        if !same_widget(&self.state, &old.state) {
+            return;
+        }
+
+        self.hovered = old.hovered;
+
+        for (&mut (_, ref mut child), &(_, ref old_child)) in self.children.iter_mut()
+                                                                .zip(old.children.iter())
+        {
+            child.inherit_state(old_child);
+        }
+    }
+
+    /// Returns the list of child numbers that lead to the node of the given widget, or `None`
+    /// if the widget isn't part of this node's hierarchy.
+    fn find_widget(&self, widget: &Arc<Widget>) -> Option<Vec<usize>> {
+        if same_widget(&self.state, widget) {
+            return Some(Vec::new());
+        }
+
+        for (num, &(_, ref child)) in self.children.iter().enumerate() {
+            if let Some(mut path) = child.find_widget(widget) {
+                path.insert(0, num);
+                return Some(path);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the widget of the node designated by `path`.
+    fn widget_at(&self, path: &[usize]) -> Arc<Widget> {
+        match path.split_first() {
+            Some((&first, rest)) => self.children[first].1.widget_at(rest),
+            None => self.state.clone(),
+        }
+    }
+
+    /// Returns the matrix that converts the coordinates of the node designated by `path` into
+    /// the coordinates of this node.
+    fn matrix_of(&self, path: &[usize]) -> Matrix {
+        path.iter().fold((Matrix::identity(), self), |(m, node), &num| {
+            let &(ref child_matrix, ref child) = &node.children[num];
+            (m * *child_matrix, child)
+        }).0
+    }
+
+    /// Converts a point in the coordinates of this node into the coordinates of the node
+    /// designated by `path`.
+    fn local_position(&self, path: &[usize], point: &[f32; 2]) -> [f32; 2] {
+        let inverse = match self.matrix_of(path).inverse() {
+            Some(m) => m,
+            None => return [0.0, 0.0],
+        };
+
+        let point = inverse * [point[0], point[1], 1.0];
+        [point[0] / point[2], point[1] / point[2]]
+    }
+
+    /// Appends to `out` all the focusable widgets of this node's hierarchy, in depth-first order.
+    fn focusable_widgets(&self, out: &mut Vec<Arc<Widget>>) {
+        if self.state.is_focusable() {
+            out.push(self.state.clone());
+        }
+
+        for &(_, ref child) in &self.children {
+            child.focusable_widgets(out);
+        }
     }
 
     /// Sends an event to the node and returns events to propagate to the parent.
-    fn send_event(&mut self, event: Box<Any>, child_num: Option<usize>) -> Vec<Box<Any>> {
+    fn send_event(&mut self, event: Box<Any>, child_num: Option<usize>, requests: &mut Requests)
+                  -> Vec<Box<Any>>
+    {
         let outcome = self.state.handle_event(&*event, child_num);
 
         if outcome.refresh_layout {
             self.needs_rebuild = true;
         }
 
+        if outcome.request_focus && requests.focus.is_none() {
+            requests.focus = Some(self.state.clone());
+        }
+
+        if outcome.capture_pointer && requests.capture.is_none() {
+            requests.capture = Some(self.state.clone());
+        }
+
+        if outcome.start_drag.is_some() && requests.drag.is_none() {
+            requests.drag = outcome.start_drag;
+        }
+
+        if outcome.clipboard.is_some() && requests.clipboard.is_none() {
+            requests.clipboard = outcome.clipboard;
+        }
+
         let mut result = outcome.events_for_parent;
         if outcome.propagate_to_parent {
             result.push(event);
@@ -496,57 +1814,107 @@ impl Node {
         result
     }
 
-    /// Sends mouse events to the node, and returns a list of events that must be propagated to the
-    /// parent.
-    fn mouse_update(&mut self, mouse: Option<[f32; 2]>, matrix: &Matrix, new_mouse_down: bool,
-                    old_mouse_down: bool) -> Vec<Box<Any>>
+    /// Sends an event to the node designated by `path`, then propagates the resulting events
+    /// through its parents up to this node. Returns the events that must be propagated to the
+    /// parent of this node.
+    fn send_event_to_path(&mut self, path: &[usize], event: Box<Any>, requests: &mut Requests)
+                          -> Vec<Box<Any>>
     {
+        let (&first, rest) = match path.split_first() {
+            Some(p) => p,
+            None => return self.send_event(event, None, requests),
+        };
+
+        let mut result = Vec::new();
+        for ev in self.children[first].1.send_event_to_path(rest, event, requests) {
+            for ev in self.send_event(ev, Some(first), requests) {
+                result.push(ev);
+            }
+        }
+        result
+    }
+
+    /// Returns the node that owns the topmost shape under `point`, by walking through the shapes
+    /// in the reverse order of `build_shapes`. The `matrix` is the absolute matrix of this node.
+    fn hit_test(&self, point: &[f32; 2], matrix: &Matrix) -> Option<Hit> {
+        if self.clip {
+            // same rectangle as the one used when drawing
+            if !ClipRect::from_matrix(matrix).contains(point) {
+                return None;
+            }
+        }
+
+        for (num, shape) in self.shapes.iter().enumerate().rev() {
+            let mode = self.state.hit_test_mode(num);
+            if mode == HitTestMode::ClickThrough {
+                continue;
+            }
+
+            if shape.clone().apply_matrix(matrix).hit_test(point) {
+                return Some(if mode == HitTestMode::Block { Hit::Blocked } else { Hit::Node(Vec::new()) });
+            }
+        }
+
+        for (num, &(ref child_matrix, ref child)) in self.children.iter().enumerate().rev() {
+            match child.hit_test(point, &(*matrix * *child_matrix)) {
+                Some(Hit::Node(mut path)) => {
+                    path.insert(0, num);
+                    return Some(Hit::Node(path));
+                },
+                Some(Hit::Blocked) => return Some(Hit::Blocked),
+                None => ()
+            }
+        }
+
+        if self.hit_area && ClipRect::from_matrix(matrix).contains(point) {
+            return Some(Hit::Node(Vec::new()));
+        }
+
+        None
+    }
+
+    /// Updates the hover state of this node and its children so that only the node designated
+    /// by `path` is hovered, and sends `MouseEnterEvent` and `MouseLeaveEvent` to the nodes whose
+    /// state changed. Returns a list of events that must be propagated to the parent.
+    fn update_hover(&mut self, path: Option<&[usize]>, requests: &mut Requests) -> Vec<Box<Any>> {
         let mut result = Vec::new();
 
         {
             let mut events_for_self = Vec::new();
 
-            for (num, &mut (ref child_matrix, ref mut child)) in self.children.iter_mut().enumerate() {
-                for ev in child.mouse_update(mouse, &(*matrix * *child_matrix), new_mouse_down,
-                                             old_mouse_down)
-                {
+            for (num, &mut (_, ref mut child)) in self.children.iter_mut().enumerate() {
+                let child_path = match path {
+                    Some(p) if p.first() == Some(&num) => Some(&p[1..]),
+                    _ => None
+                };
+
+                for ev in child.update_hover(child_path, requests) {
                     events_for_self.push((ev, num));
                 }
-
-                // TODO: break if event handled
             }
 
             for (ev, child) in events_for_self {
-                for ev in self.send_event(ev, Some(child)) {
+                for ev in self.send_event(ev, Some(child), requests) {
                     result.push(ev);
                 }
             }
         }
 
-        let hit = if let Some(mouse) = mouse {
-            self.shapes.iter().find(|s| (*s).clone().apply_matrix(matrix).hit_test(&mouse)).is_some()
-        } else {
-            false
+        let hit = match path {
+            Some(p) => p.is_empty(),
+            None => false,
         };
 
-        // TODO: do not send these events if not necessary (eg. do not send mouse leave if mouse
-        // wasn't over the element)
-        if hit {
-            let ev = Box::new(predefined::MouseEnterEvent) as Box<Any>;
-            for ev in self.send_event(ev, None) {
-                result.push(ev);
-            }
+        if hit != self.hovered {
+            self.hovered = hit;
 
-        } else {
-            let ev = Box::new(predefined::MouseLeaveEvent) as Box<Any>;
-            for ev in self.send_event(ev, None) {
-                result.push(ev);
-            }
-        };
+            let ev = if hit {
+                Box::new(predefined::MouseEnterEvent) as Box<Any>
+            } else {
+                Box::new(predefined::MouseLeaveEvent) as Box<Any>
+            };
 
-        if hit && !new_mouse_down && old_mouse_down {
-            let ev = Box::new(predefined::MouseClick) as Box<Any>;
-            for ev in self.send_event(ev, None) {
+            for ev in self.send_event(ev, None, requests) {
                 result.push(ev);
             }
         }
@@ -0,0 +1,532 @@
+extern crate eui;
+
+use std::any::Any;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Widget that records the name of the events it receives.
+struct Recorder {
+    focusable: bool,
+    hit_test_mode: eui::HitTestMode,
+    events: Mutex<Vec<&'static str>>,
+    last_position: Mutex<Option<[f32; 2]>>,
+}
+
+impl Recorder {
+    fn new(focusable: bool) -> Arc<Recorder> {
+        Recorder::with_mode(focusable, eui::HitTestMode::Normal)
+    }
+
+    fn with_mode(focusable: bool, mode: eui::HitTestMode) -> Arc<Recorder> {
+        Arc::new(Recorder {
+            focusable: focusable,
+            hit_test_mode: mode,
+            events: Mutex::new(Vec::new()),
+            last_position: Mutex::new(None),
+        })
+    }
+
+    fn take(&self) -> Vec<&'static str> {
+        let mut events = self.events.lock().unwrap();
+        let result = events.clone();
+        events.clear();
+        result
+    }
+}
+
+impl eui::Widget for Recorder {
+    fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+        let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+        eui::Layout::Shapes(vec![s])
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.focusable
+    }
+
+    fn hit_test_mode(&self, _: usize) -> eui::HitTestMode {
+        self.hit_test_mode
+    }
+
+    fn handle_event(&self, event: &Any, _: Option<usize>) -> eui::EventOutcome {
+        let name = if event.is::<eui::predefined::KeyDownEvent>() {
+            "key_down"
+        } else if event.is::<eui::predefined::KeyUpEvent>() {
+            "key_up"
+        } else if event.is::<eui::predefined::CharacterEvent>() {
+            "character"
+        } else if event.is::<eui::predefined::FocusGainedEvent>() {
+            "focus_gained"
+        } else if event.is::<eui::predefined::FocusLostEvent>() {
+            "focus_lost"
+        } else if event.is::<eui::predefined::MouseEnterEvent>() {
+            "mouse_enter"
+        } else if event.is::<eui::predefined::MouseLeaveEvent>() {
+            "mouse_leave"
+        } else if let Some(ev) = event.downcast_ref::<eui::predefined::MouseDownEvent>() {
+            *self.last_position.lock().unwrap() = Some(ev.position);
+            if ev.button == eui::MouseButton::Right { "right_down" } else { "mouse_down" }
+        } else if let Some(ev) = event.downcast_ref::<eui::predefined::MouseUpEvent>() {
+            *self.last_position.lock().unwrap() = Some(ev.position);
+            if ev.button == eui::MouseButton::Right { "right_up" } else { "mouse_up" }
+        } else if let Some(ev) = event.downcast_ref::<eui::predefined::MouseClick>() {
+            if ev.button == eui::MouseButton::Right { "right_click" } else { "click" }
+        } else if event.is::<eui::predefined::MouseDoubleClick>() {
+            "double_click"
+        } else if event.is::<eui::predefined::TouchStartEvent>() {
+            "touch_start"
+        } else if let Some(ev) = event.downcast_ref::<eui::predefined::TouchMoveEvent>() {
+            *self.last_position.lock().unwrap() = Some(ev.position);
+            "touch_move"
+        } else if event.is::<eui::predefined::TouchEndEvent>() {
+            "touch_end"
+        } else {
+            return Default::default();
+        };
+
+        self.events.lock().unwrap().push(name);
+        Default::default()
+    }
+}
+
+/// Widget that puts its children in a horizontal bar.
+struct Bar(Vec<Arc<Recorder>>);
+
+impl eui::Widget for Bar {
+    fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+        eui::Layout::HorizontalBar {
+            alignment: eui::HorizontalAlignment::Center,
+            vertical_align: false,
+            children: self.0.iter().map(|c| {
+                eui::Child { child: c.clone(), weight: 1, collapse: false,
+                             alignment: Default::default(), padding_top: 0.0, padding_left: 0.0,
+                             padding_bottom: 0.0, padding_right: 0.0 }
+            }).collect(),
+        }
+    }
+}
+
+/// Widget that puts its children on top of each other.
+struct Stack(Vec<Arc<Recorder>>);
+
+impl eui::Widget for Stack {
+    fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+        eui::Layout::AbsolutePositionned(self.0.iter().map(|c| {
+            (eui::Matrix::identity(), c.clone() as Arc<eui::Widget>)
+        }).collect())
+    }
+}
+
+#[test]
+fn tab_traversal() {
+    let a = Recorder::new(true);
+    let b = Recorder::new(false);
+    let c = Recorder::new(true);
+    let ui = eui::Ui::new(Bar(vec![a.clone(), b.clone(), c.clone()]), 1.0);
+    assert!(!ui.has_focus());
+
+    ui.key_down(eui::Key::Tab, Default::default());
+    assert_eq!(a.take(), &["focus_gained"]);
+    assert!(ui.has_focus());
+
+    ui.key_down(eui::Key::Tab, Default::default());
+    assert_eq!(a.take(), &["key_down", "focus_lost"]);
+    assert_eq!(b.take(), Vec::<&str>::new());
+    assert_eq!(c.take(), &["focus_gained"]);
+
+    let shift = eui::Modifiers { shift: true, .. Default::default() };
+    ui.key_down(eui::Key::Tab, shift);
+    assert_eq!(c.take(), &["key_down", "focus_lost"]);
+    assert_eq!(a.take(), &["focus_gained"]);
+
+    ui.clear_focus();
+    assert_eq!(a.take(), &["focus_lost"]);
+    assert!(!ui.has_focus());
+}
+
+#[test]
+fn keyboard_goes_to_focused() {
+    let a = Recorder::new(true);
+    let b = Recorder::new(true);
+    let ui = eui::Ui::new(Bar(vec![a.clone(), b.clone()]), 1.0);
+
+    ui.focus_previous();
+    assert_eq!(b.take(), &["focus_gained"]);
+
+    ui.key_down(eui::Key::Char('a'), Default::default());
+    ui.character('a');
+    ui.key_up(eui::Key::Char('a'), Default::default());
+    assert_eq!(a.take(), Vec::<&str>::new());
+    assert_eq!(b.take(), &["key_down", "character", "key_up"]);
+}
+
+#[test]
+fn focus_requests() {
+    /// Widget that puts some text in the clipboard when it gains the focus.
+    struct Copier;
+
+    impl eui::Widget for Copier {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::Shapes(Vec::new())
+        }
+
+        fn is_focusable(&self) -> bool {
+            true
+        }
+
+        fn handle_event(&self, event: &Any, _: Option<usize>) -> eui::EventOutcome {
+            if event.is::<eui::predefined::FocusGainedEvent>() {
+                eui::EventOutcome { clipboard: Some("focused".to_owned()), .. Default::default() }
+            } else {
+                Default::default()
+            }
+        }
+    }
+
+    let ui = eui::Ui::new(Copier, 1.0);
+    let clipboard = Arc::new(eui::MemoryClipboard::new());
+    ui.set_clipboard(clipboard.clone());
+
+    ui.focus_next();
+    assert_eq!(eui::Clipboard::get_text(&clipboard), Some("focused".to_owned()));
+}
+
+#[test]
+fn hover_transitions() {
+    let a = Recorder::new(false);
+    let b = Recorder::new(false);
+    let ui = eui::Ui::new(Bar(vec![a.clone(), b.clone()]), 1.0);
+    ui.draw();
+    assert!(!ui.is_hovering());
+
+    ui.set_cursor(Some([-0.5, 0.0]), false);
+    assert_eq!(a.take(), &["mouse_enter"]);
+    assert_eq!(b.take(), Vec::<&str>::new());
+    assert!(ui.is_hovering());
+
+    ui.set_cursor(Some([-0.4, 0.2]), false);
+    assert_eq!(a.take(), Vec::<&str>::new());
+
+    ui.set_cursor(Some([0.5, 0.0]), false);
+    assert_eq!(a.take(), &["mouse_leave"]);
+    assert_eq!(b.take(), &["mouse_enter"]);
+
+    ui.rebuild();
+    assert_eq!(b.take(), Vec::<&str>::new());
+    assert!(ui.is_hovering());
+
+    ui.set_cursor(None, false);
+    assert_eq!(b.take(), &["mouse_leave"]);
+    assert!(!ui.is_hovering());
+}
+
+#[test]
+fn click_goes_to_topmost() {
+    let bottom = Recorder::new(false);
+    let top = Recorder::new(false);
+    let ui = eui::Ui::new(Stack(vec![bottom.clone(), top.clone()]), 1.0);
+
+    ui.set_cursor(Some([0.0, 0.0]), true);
+    ui.set_cursor(Some([0.0, 0.0]), false);
+    assert_eq!(top.take(), &["mouse_enter", "mouse_down", "mouse_up", "click"]);
+    assert_eq!(bottom.take(), Vec::<&str>::new());
+}
+
+#[test]
+fn click_through() {
+    let bottom = Recorder::new(false);
+    let top = Recorder::with_mode(false, eui::HitTestMode::ClickThrough);
+    let ui = eui::Ui::new(Stack(vec![bottom.clone(), top.clone()]), 1.0);
+
+    ui.set_cursor(Some([0.0, 0.0]), true);
+    ui.set_cursor(Some([0.0, 0.0]), false);
+    assert_eq!(top.take(), Vec::<&str>::new());
+    assert_eq!(bottom.take(), &["mouse_enter", "mouse_down", "mouse_up", "click"]);
+}
+
+#[test]
+fn event_blocker() {
+    let bottom = Recorder::new(false);
+    let top = Recorder::with_mode(false, eui::HitTestMode::Block);
+    let ui = eui::Ui::new(Stack(vec![bottom.clone(), top.clone()]), 1.0);
+
+    ui.set_cursor(Some([0.0, 0.0]), true);
+    ui.set_cursor(Some([0.0, 0.0]), false);
+    assert_eq!(top.take(), Vec::<&str>::new());
+    assert_eq!(bottom.take(), Vec::<&str>::new());
+    assert!(ui.is_hovering());
+}
+
+#[test]
+fn mouse_buttons() {
+    let a = Recorder::new(false);
+    let b = Recorder::new(false);
+    let ui = eui::Ui::new(Bar(vec![a.clone(), b.clone()]), 1.0);
+
+    ui.set_cursor_position(Some([-0.5, 0.5]));
+    ui.set_mouse_button(eui::MouseButton::Right, true);
+    assert_eq!(a.take(), &["mouse_enter", "right_down"]);
+    assert_eq!(*a.last_position.lock().unwrap(), Some([0.0, 0.5]));
+
+    ui.set_mouse_button(eui::MouseButton::Right, false);
+    assert_eq!(a.take(), &["right_up", "right_click"]);
+
+    // releasing over another widget is not a click
+    ui.set_mouse_button(eui::MouseButton::Left, true);
+    ui.set_cursor_position(Some([0.5, 0.0]));
+    ui.set_mouse_button(eui::MouseButton::Left, false);
+    assert_eq!(a.take(), &["mouse_down", "mouse_leave"]);
+    assert_eq!(b.take(), &["mouse_enter", "mouse_up"]);
+}
+
+#[test]
+fn double_click() {
+    let a = Recorder::new(false);
+    let ui = eui::Ui::new(Bar(vec![a.clone()]), 1.0);
+
+    for _ in 0 .. 2 {
+        ui.set_cursor(Some([0.0, 0.0]), true);
+        ui.set_cursor(Some([0.0, 0.0]), false);
+    }
+    assert_eq!(a.take(), &["mouse_enter", "mouse_down", "mouse_up", "click",
+                           "mouse_down", "mouse_up", "click", "double_click"]);
+
+    ui.set_double_click_interval(0);
+    for _ in 0 .. 2 {
+        ui.set_cursor(Some([0.0, 0.0]), true);
+        ui.set_cursor(Some([0.0, 0.0]), false);
+    }
+    assert_eq!(a.take(), &["mouse_down", "mouse_up", "click", "mouse_down", "mouse_up", "click"]);
+}
+
+#[test]
+fn scroll_wheel() {
+    let content = Recorder::new(false);
+    let ui = eui::Ui::new(eui::predefined::ScrollView::new(content.clone(), 1.0, 3.0), 1.0);
+    ui.draw();
+
+    ui.scroll([0.0, -1.0]);
+    assert_eq!(ui.widget().scroll_position(), [0.0, 0.0]);
+
+    ui.set_cursor_position(Some([0.0, 0.0]));
+    ui.scroll([0.0, -2.0]);
+    assert_eq!(ui.widget().scroll_position(), [0.0, 0.1]);
+
+    ui.scroll([0.0, 100.0]);
+    assert_eq!(ui.widget().scroll_position(), [0.0, 0.0]);
+}
+
+#[test]
+fn scroll_over_gap() {
+    /// Widget whose only child is a small square in its top-left corner.
+    struct Corner(Arc<Recorder>);
+
+    impl eui::Widget for Corner {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let matrix = eui::Matrix::translate(-0.9, 0.9) * eui::Matrix::scale(0.1);
+            eui::Layout::AbsolutePositionned(vec![(matrix, self.0.clone())])
+        }
+    }
+
+    let content = Arc::new(Corner(Recorder::new(false)));
+    let ui = eui::Ui::new(eui::predefined::ScrollView::new(content, 1.0, 3.0), 1.0);
+    ui.draw();
+
+    // the cursor is over the scroll view but not over its content
+    ui.set_cursor_position(Some([0.9, 0.9]));
+    assert!(ui.is_hovering());
+    ui.scroll([0.0, -1.0]);
+    assert_eq!(ui.widget().scroll_position(), [0.0, 0.05]);
+
+    // outside of the scroll view, nothing happens
+    ui.set_cursor_position(Some([1.5, 0.0]));
+    ui.scroll([0.0, -1.0]);
+    assert_eq!(ui.widget().scroll_position(), [0.0, 0.05]);
+}
+
+#[test]
+fn drag_and_drop() {
+    struct Slot {
+        item: Mutex<Option<u32>>,
+        events: Mutex<Vec<&'static str>>,
+    }
+
+    impl eui::Widget for Slot {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+
+        fn handle_event(&self, event: &Any, _: Option<usize>) -> eui::EventOutcome {
+            if event.is::<eui::predefined::DragStartEvent>() {
+                self.events.lock().unwrap().push("drag_start");
+                let item = match self.item.lock().unwrap().take() {
+                    Some(i) => i,
+                    None => return Default::default(),
+                };
+
+                let ghost = eui::Shape::Image { name: "ghost".to_owned(),
+                                                matrix: eui::Matrix::scale(0.1), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+                eui::EventOutcome {
+                    start_drag: Some(eui::Drag { payload: Box::new(item), ghost: vec![ghost] }),
+                    .. Default::default()
+                }
+
+            } else if event.is::<eui::predefined::DragMoveEvent>() {
+                self.events.lock().unwrap().push("drag_move");
+                Default::default()
+
+            } else if let Some(ev) = event.downcast_ref::<eui::predefined::DropEvent>() {
+                self.events.lock().unwrap().push("drop");
+                *self.item.lock().unwrap() = ev.payload.downcast_ref::<u32>().cloned();
+                Default::default()
+
+            } else if event.is::<eui::predefined::DragEndEvent>() {
+                self.events.lock().unwrap().push("drag_end");
+                Default::default()
+
+            } else if event.is::<eui::predefined::MouseClick>() {
+                self.events.lock().unwrap().push("click");
+                Default::default()
+
+            } else {
+                Default::default()
+            }
+        }
+    }
+
+    struct Inventory(Vec<Arc<Slot>>);
+    impl eui::Widget for Inventory {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::HorizontalBar {
+                alignment: eui::HorizontalAlignment::Center,
+                vertical_align: false,
+                children: self.0.iter().map(|c| {
+                    eui::Child { child: c.clone(), weight: 1, collapse: false,
+                                 alignment: Default::default(), padding_top: 0.0,
+                                 padding_left: 0.0, padding_bottom: 0.0, padding_right: 0.0 }
+                }).collect(),
+            }
+        }
+    }
+
+    let a = Arc::new(Slot { item: Mutex::new(Some(42)), events: Mutex::new(Vec::new()) });
+    let b = Arc::new(Slot { item: Mutex::new(None), events: Mutex::new(Vec::new()) });
+    let ui = eui::Ui::new(Inventory(vec![a.clone(), b.clone()]), 1.0);
+
+    ui.set_cursor(Some([-0.5, 0.0]), true);
+    ui.set_cursor_position(Some([-0.49, 0.0]));
+    assert!(a.events.lock().unwrap().is_empty());
+    assert_eq!(ui.draw().len(), 2);
+
+    ui.set_cursor_position(Some([-0.4, 0.0]));
+    assert_eq!(*a.events.lock().unwrap(), &["drag_start"]);
+
+    let shapes = ui.draw();
+    assert_eq!(shapes.len(), 3);
+    assert_eq!(shapes[2], eui::Shape::Image {
+        name: "ghost".to_owned(),
+        matrix: eui::Matrix::translate(-0.4, 0.0) * eui::Matrix::scale(0.1),
+        uv: eui::UvRect::full(),
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+
+    ui.set_cursor(Some([0.5, 0.0]), false);
+    assert_eq!(*a.events.lock().unwrap(), &["drag_start", "drag_end"]);
+    assert_eq!(*b.events.lock().unwrap(), &["drag_move", "drop"]);
+    assert_eq!(*a.item.lock().unwrap(), None);
+    assert_eq!(*b.item.lock().unwrap(), Some(42));
+    assert_eq!(ui.draw().len(), 2);
+}
+
+#[test]
+fn multi_touch() {
+    let a = Recorder::new(false);
+    let b = Recorder::new(false);
+    let ui = eui::Ui::new(Bar(vec![a.clone(), b.clone()]), 1.0);
+
+    ui.touch_start(1, [-0.5, 0.0]);
+    assert_eq!(a.take(), &["touch_start", "mouse_enter", "mouse_down"]);
+
+    ui.touch_start(7, [0.5, 0.0]);
+    assert_eq!(b.take(), &["touch_start"]);
+
+    // the touch keeps its target even when moving away from it
+    ui.touch_move(7, [-0.5, 0.0]);
+    assert_eq!(b.take(), &["touch_move"]);
+    assert_eq!(*b.last_position.lock().unwrap(), Some([-2.0, 0.0]));
+    assert_eq!(a.take(), Vec::<&str>::new());
+
+    ui.touch_end(1);
+    assert_eq!(a.take(), &["touch_end", "mouse_up", "click", "mouse_leave"]);
+    assert!(!ui.is_hovering());
+
+    ui.touch_end(7);
+    assert_eq!(b.take(), &["touch_end"]);
+    assert_eq!(a.take(), Vec::<&str>::new());
+}
+
+#[test]
+fn pointer_capture() {
+    struct Slider {
+        events: Mutex<Vec<(&'static str, [f32; 2])>>,
+    }
+
+    impl eui::Widget for Slider {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+
+        fn handle_event(&self, event: &Any, _: Option<usize>) -> eui::EventOutcome {
+            let mut events = self.events.lock().unwrap();
+
+            if let Some(ev) = event.downcast_ref::<eui::predefined::MouseDownEvent>() {
+                events.push(("down", ev.position));
+                eui::EventOutcome { capture_pointer: true, .. Default::default() }
+            } else if let Some(ev) = event.downcast_ref::<eui::predefined::MouseMoveEvent>() {
+                events.push(("move", ev.position));
+                Default::default()
+            } else if let Some(ev) = event.downcast_ref::<eui::predefined::MouseUpEvent>() {
+                events.push(("up", ev.position));
+                Default::default()
+            } else {
+                Default::default()
+            }
+        }
+    }
+
+    struct TestedWidget(Arc<Slider>, Arc<Recorder>);
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let child = |w: Arc<eui::Widget>| {
+                eui::Child { child: w, weight: 1, collapse: false,
+                             alignment: Default::default(), padding_top: 0.0, padding_left: 0.0,
+                             padding_bottom: 0.0, padding_right: 0.0 }
+            };
+
+            eui::Layout::HorizontalBar {
+                alignment: eui::HorizontalAlignment::Center,
+                vertical_align: false,
+                children: vec![child(self.0.clone()), child(self.1.clone())],
+            }
+        }
+    }
+
+    let slider = Arc::new(Slider { events: Mutex::new(Vec::new()) });
+    let other = Recorder::new(false);
+    let ui = eui::Ui::new(TestedWidget(slider.clone(), other.clone()), 1.0);
+
+    ui.set_cursor(Some([-0.5, 0.0]), true);
+    ui.set_cursor_position(Some([0.5, 0.0]));
+    ui.set_cursor(Some([0.75, 0.0]), false);
+    assert_eq!(*slider.events.lock().unwrap(), &[("move", [0.0, 0.0]), ("down", [0.0, 0.0]),
+                                                 ("move", [2.0, 0.0]), ("move", [2.5, 0.0]),
+                                                 ("up", [2.5, 0.0])]);
+    assert_eq!(other.take(), &["mouse_enter"]);
+
+    // the capture ends when the button is released
+    slider.events.lock().unwrap().clear();
+    ui.set_cursor_position(Some([0.5, 0.0]));
+    assert!(slider.events.lock().unwrap().is_empty());
+}
@@ -1,13 +1,14 @@
 extern crate eui;
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 #[test]
 fn basic() {
     struct FullWidget;
     impl eui::Widget for FullWidget {
         fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
-            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity() };
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
             eui::Layout::Shapes(vec![s])
         }
     }
@@ -15,7 +16,7 @@ fn basic() {
     let ui = eui::Ui::new(FullWidget, 1.0);
     let shapes = ui.draw();
     assert_eq!(shapes,
-               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity() }]);
+               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] }]);
 }
 
 #[test]
@@ -23,7 +24,7 @@ fn horizontal_split_two() {
     struct FullWidget;
     impl eui::Widget for FullWidget {
         fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
-            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity() };
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
             eui::Layout::Shapes(vec![s])
         }
     }
@@ -49,8 +50,8 @@ fn horizontal_split_two() {
     let ui = eui::Ui::new(TestedWidget, 1.0);
     let shapes = ui.draw();
     assert_eq!(shapes,
-               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(-0.5, 0.0) * eui::Matrix::scale_wh(0.5, 1.0) },
-                 eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(0.5, 0.0) * eui::Matrix::scale_wh(0.5, 1.0) }]);
+               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(-0.5, 0.0) * eui::Matrix::scale_wh(0.5, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] },
+                 eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(0.5, 0.0) * eui::Matrix::scale_wh(0.5, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] }]);
 }
 
 #[test]
@@ -58,7 +59,7 @@ fn horizontal_collapse() {
     struct HalfWidthWidget;
     impl eui::Widget for HalfWidthWidget {
         fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
-            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::scale_wh(0.5, 1.0) };
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::scale_wh(0.5, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
             eui::Layout::Shapes(vec![s])
         }
     }
@@ -84,6 +85,372 @@ fn horizontal_collapse() {
     let ui = eui::Ui::new(TestedWidget, 1.0);
     let shapes = ui.draw();
     assert_eq!(shapes,
-               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(-0.25, 0.0) * eui::Matrix::scale_wh(0.25, 1.0) },
-                 eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(0.25, 0.0) * eui::Matrix::scale_wh(0.25, 1.0) }]);
+               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(-0.25, 0.0) * eui::Matrix::scale_wh(0.25, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] },
+                 eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(0.25, 0.0) * eui::Matrix::scale_wh(0.25, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] }]);
+}
+
+#[test]
+fn scroll_view() {
+    struct NamedWidget(&'static str);
+    impl eui::Widget for NamedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: self.0.to_owned(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+    }
+
+    struct Content;
+    impl eui::Widget for Content {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::VerticalBar {
+                alignment: eui::VerticalAlignment::Center,
+                horizontal_align: false,
+                children: vec![
+                    eui::Child { child: Arc::new(NamedWidget("bottom")), weight: 1, collapse: false,
+                                 alignment: Default::default(), padding_top: 0.0, padding_left: 0.0,
+                                 padding_bottom: 0.0, padding_right: 0.0 },
+                    eui::Child { child: Arc::new(NamedWidget("top")), weight: 1, collapse: false,
+                                 alignment: Default::default(), padding_top: 0.0, padding_left: 0.0,
+                                 padding_bottom: 0.0, padding_right: 0.0 },
+                ],
+            }
+        }
+    }
+
+    struct TestedWidget(f32);
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::ScrollView {
+                child: Arc::new(Content),
+                content_size: [1.0, 2.0],
+                scroll: [0.0, self.0],
+            }
+        }
+    }
+
+    let ui = eui::Ui::new(TestedWidget(0.0), 1.0);
+    let shapes = ui.draw();
+    assert_eq!(shapes,
+               &[eui::Shape::Image { name: "top".to_owned(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] }]);
+
+    let ui = eui::Ui::new(TestedWidget(1.0), 1.0);
+    let shapes = ui.draw();
+    assert_eq!(shapes,
+               &[eui::Shape::Image { name: "bottom".to_owned(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] }]);
+}
+
+#[test]
+fn incremental_rebuild() {
+    struct Leaf {
+        build_count: Arc<AtomicUsize>,
+        dirty: AtomicBool,
+        half: AtomicBool,
+    }
+
+    impl eui::Widget for Leaf {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            self.build_count.fetch_add(1, Ordering::SeqCst);
+            let width = if self.half.load(Ordering::SeqCst) { 0.5 } else { 1.0 };
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::scale_wh(width, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+
+        fn needs_rebuild(&self) -> bool {
+            self.dirty.swap(false, Ordering::SeqCst)
+        }
+    }
+
+    struct TestedWidget {
+        build_count: Arc<AtomicUsize>,
+        leaves: Vec<Arc<Leaf>>,
+    }
+
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            self.build_count.fetch_add(1, Ordering::SeqCst);
+            eui::Layout::HorizontalBar {
+                alignment: eui::HorizontalAlignment::Center,
+                vertical_align: false,
+                children: self.leaves.iter().map(|l| {
+                    eui::Child { child: l.clone(), weight: 1, collapse: true,
+                                 alignment: Default::default(), padding_top: 0.0, padding_left: 0.0,
+                                 padding_bottom: 0.0, padding_right: 0.0 }
+                }).collect(),
+            }
+        }
+    }
+
+    let build_count = Arc::new(AtomicUsize::new(0));
+    let leaves: Vec<_> = (0 .. 4).map(|_| Arc::new(Leaf {
+        build_count: build_count.clone(),
+        dirty: AtomicBool::new(false),
+        half: AtomicBool::new(false),
+    })).collect();
+
+    let ui = eui::Ui::new(TestedWidget { build_count: build_count.clone(), leaves: leaves.clone() }, 1.0);
+    ui.draw();
+    assert_eq!(build_count.load(Ordering::SeqCst), 5);
+
+    // a leaf whose size doesn't change is the only one to be rebuilt
+    leaves[1].dirty.store(true, Ordering::SeqCst);
+    ui.draw();
+    assert_eq!(build_count.load(Ordering::SeqCst), 6);
+
+    // a leaf whose size changes also rebuilds its parent, but not its siblings
+    leaves[1].half.store(true, Ordering::SeqCst);
+    leaves[1].dirty.store(true, Ordering::SeqCst);
+    let shapes = ui.draw();
+    assert_eq!(build_count.load(Ordering::SeqCst), 8);
+
+    let reference = eui::Ui::new(TestedWidget { build_count: build_count.clone(), leaves: leaves.clone() }, 1.0);
+    assert_eq!(shapes, reference.draw());
+}
+
+#[test]
+fn cached_draw_list() {
+    struct TestedWidget {
+        dirty: AtomicBool,
+    }
+
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+
+        fn needs_rebuild(&self) -> bool {
+            self.dirty.swap(false, Ordering::SeqCst)
+        }
+    }
+
+    let ui = eui::Ui::new(TestedWidget { dirty: AtomicBool::new(false) }, 1.0);
+    let first = ui.draw_list();
+    assert_eq!(&*first,
+               &[eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] }]);
+
+    // nothing changed
+    let second = ui.draw_list();
+    assert_eq!(first.generation(), second.generation());
+
+    // the widget is dirty
+    ui.widget().dirty.store(true, Ordering::SeqCst);
+    let third = ui.draw_list();
+    assert!(third.generation() > second.generation());
+    assert_eq!(&*third, &*first);
+
+    // changing the viewport also generates a new list
+    ui.set_viewport_height_per_width(2.0);
+    assert!(ui.draw_list().generation() > third.generation());
+}
+
+#[test]
+fn colored_shapes() {
+    struct TestedWidget;
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::Shapes(vec![
+                eui::Shape::Rectangle { matrix: eui::Matrix::identity(), color: [0.0, 0.0, 1.0, 1.0] },
+                eui::Shape::Image { name: String::new(), matrix: eui::Matrix::scale(0.5),
+                                    uv: eui::UvRect::full(),
+                                    tint: [1.0, 0.0, 0.0, 0.5] },
+            ])
+        }
+    }
+
+    struct Parent;
+    impl eui::Widget for Parent {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::AbsolutePositionned(vec![
+                (eui::Matrix::translate(0.5, 0.0) * eui::Matrix::scale(0.5), Arc::new(TestedWidget))
+            ])
+        }
+    }
+
+    let ui = eui::Ui::new(Parent, 1.0);
+    let shapes = ui.draw();
+    assert_eq!(shapes,
+               &[eui::Shape::Rectangle { matrix: eui::Matrix::translate(0.5, 0.0) * eui::Matrix::scale(0.5),
+                                         color: [0.0, 0.0, 1.0, 1.0] },
+                 eui::Shape::Image { name: String::new(), matrix: eui::Matrix::translate(0.5, 0.0) * eui::Matrix::scale(0.25),
+                                     uv: eui::UvRect::full(), tint: [1.0, 0.0, 0.0, 0.5] }]);
+
+    assert_eq!(shapes[0].get_bounding_box(), (0.5, 1.0, -0.5, 0.0));
+    assert!(shapes[0].hit_test(&[0.9, 0.4]));
+    assert!(!shapes[0].hit_test(&[-0.1, 0.0]));
+}
+
+#[test]
+fn clipped() {
+    struct FullWidget;
+    impl eui::Widget for FullWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Rectangle { matrix: eui::Matrix::identity(), color: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+    }
+
+    struct Clipper;
+    impl eui::Widget for Clipper {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            // the first child is partially visible, the second one is entirely outside
+            eui::Layout::Clipped(Box::new(eui::Layout::AbsolutePositionned(vec![
+                (eui::Matrix::translate(1.0, 0.0), Arc::new(FullWidget)),
+                (eui::Matrix::translate(3.0, 0.0), Arc::new(FullWidget)),
+            ])))
+        }
+    }
+
+    struct TestedWidget;
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::AbsolutePositionned(vec![(eui::Matrix::scale(0.5), Arc::new(Clipper))])
+        }
+    }
+
+    let ui = eui::Ui::new(TestedWidget, 1.0);
+    let list = ui.draw_list();
+    assert_eq!(&*list,
+               &[eui::Shape::Rectangle { matrix: eui::Matrix::scale(0.5) * eui::Matrix::translate(1.0, 0.0),
+                                         color: [1.0, 1.0, 1.0, 1.0] }]);
+    assert_eq!(list.clip_rects(),
+               &[Some(eui::ClipRect { top: 0.5, right: 0.5, bottom: -0.5, left: -0.5 })]);
+
+    // the visible part of the shape can be hovered, but not the clipped part
+    ui.set_cursor(Some([0.25, 0.0]), false);
+    assert!(ui.is_hovering());
+    ui.set_cursor(Some([0.75, 0.0]), false);
+    assert!(!ui.is_hovering());
+}
+
+#[test]
+fn tinted() {
+    struct Red;
+    impl eui::Widget for Red {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::Tinted {
+                tint: [1.0, 0.0, 0.0, 1.0],
+                layout: Box::new(eui::Layout::Shapes(vec![
+                    eui::Shape::Rectangle { matrix: eui::Matrix::identity(), color: [1.0, 1.0, 1.0, 1.0] },
+                    eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(),
+                                        uv: eui::UvRect::full(),
+                                        tint: [0.5, 0.5, 0.5, 1.0] },
+                ])),
+            }
+        }
+    }
+
+    struct TestedWidget;
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::Tinted {
+                tint: [1.0, 1.0, 1.0, 0.5],
+                layout: Box::new(eui::Layout::AbsolutePositionned(vec![
+                    (eui::Matrix::identity(), Arc::new(Red))
+                ])),
+            }
+        }
+    }
+
+    let ui = eui::Ui::new(TestedWidget, 1.0);
+    assert_eq!(ui.draw(),
+               &[eui::Shape::Rectangle { matrix: eui::Matrix::identity(), color: [1.0, 0.0, 0.0, 0.5] },
+                 eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(),
+                                     uv: eui::UvRect::full(), tint: [0.5, 0.0, 0.0, 0.5] }]);
+}
+
+#[test]
+fn atlas() {
+    struct TestedWidget;
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: "sword".to_owned(), matrix: eui::Matrix::identity(),
+                                        uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+    }
+
+    let ui = eui::Ui::new(TestedWidget, 1.0);
+    let before = ui.draw_list();
+
+    let mut atlas = eui::Atlas::new();
+    atlas.add_frame("sword", "icons", eui::UvRect { top: 1.0, right: 0.5, bottom: 0.5, left: 0.0 });
+    ui.set_atlas(atlas);
+
+    let after = ui.draw_list();
+    assert!(after.generation() > before.generation());
+    assert_eq!(&*after,
+               &[eui::Shape::Image { name: "icons".to_owned(), matrix: eui::Matrix::identity(),
+                                     uv: eui::UvRect { top: 1.0, right: 0.5, bottom: 0.5, left: 0.0 },
+                                     tint: [1.0, 1.0, 1.0, 1.0] }]);
+}
+
+#[test]
+fn grid() {
+    struct FullWidget;
+    impl eui::Widget for FullWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::identity(), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+    }
+
+    struct HalfWidthWidget;
+    impl eui::Widget for HalfWidthWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let s = eui::Shape::Image { name: String::new(), matrix: eui::Matrix::scale_wh(0.5, 1.0), uv: eui::UvRect::full(), tint: [1.0, 1.0, 1.0, 1.0] };
+            eui::Layout::Shapes(vec![s])
+        }
+    }
+
+    struct TestedWidget;
+    impl eui::Widget for TestedWidget {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            let left = eui::Alignment { horizontal: eui::HorizontalAlignment::Left, .. Default::default() };
+
+            eui::Layout::Grid(eui::Grid {
+                columns: vec![1, 3],
+                rows: vec![1, 1],
+                children: vec![
+                    eui::GridChild { child: Arc::new(FullWidget), column: 0, row: 0,
+                                     column_span: 1, row_span: 1, alignment: Default::default() },
+                    eui::GridChild { child: Arc::new(FullWidget), column: 1, row: 0,
+                                     column_span: 1, row_span: 2, alignment: Default::default() },
+                    eui::GridChild { child: Arc::new(HalfWidthWidget), column: 0, row: 1,
+                                     column_span: 1, row_span: 1, alignment: left },
+                    // outside of the grid
+                    eui::GridChild { child: Arc::new(FullWidget), column: 1, row: 1,
+                                     column_span: 2, row_span: 1, alignment: Default::default() },
+                ],
+            })
+        }
+    }
+
+    let ui = eui::Ui::new(TestedWidget, 1.0);
+    let boxes: Vec<_> = ui.draw().iter().map(|s| s.get_bounding_box()).collect();
+    let expected = [(1.0, -0.5, 0.0, -1.0), (1.0, 1.0, -1.0, -0.5), (0.0, -0.75, -1.0, -1.0)];
+
+    assert_eq!(boxes.len(), expected.len());
+    for (b, e) in boxes.iter().zip(expected.iter()) {
+        assert!((b.0 - e.0).abs() < 1e-5 && (b.1 - e.1).abs() < 1e-5 &&
+                (b.2 - e.2).abs() < 1e-5 && (b.3 - e.3).abs() < 1e-5, "{:?} != {:?}", b, e);
+    }
+
+    // weights that add up to zero are treated as equal
+    struct ZeroWeights;
+    impl eui::Widget for ZeroWeights {
+        fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+            eui::Layout::Grid(eui::Grid {
+                columns: vec![0, 0],
+                rows: vec![0],
+                children: vec![
+                    eui::GridChild { child: Arc::new(FullWidget), column: 1, row: 0,
+                                     column_span: 1, row_span: 1, alignment: Default::default() },
+                ],
+            })
+        }
+    }
+
+    let ui = eui::Ui::new(ZeroWeights, 1.0);
+    let boxes: Vec<_> = ui.draw().iter().map(|s| s.get_bounding_box()).collect();
+    assert_eq!(boxes, &[(1.0, 1.0, -1.0, 0.0)]);
 }
@@ -0,0 +1,95 @@
+extern crate eui;
+
+use eui::predefined::NineSliceImage;
+use eui::Matrix;
+
+fn image(name: &str, matrix: Matrix) -> eui::Shape {
+    eui::Shape::Image { name: name.to_owned(), matrix: matrix, uv: eui::UvRect::full(),
+                        tint: [1.0, 1.0, 1.0, 1.0] }
+}
+
+fn quarter() -> Matrix {
+    Matrix([[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]])
+}
+
+fn half() -> Matrix {
+    Matrix([[-1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]])
+}
+
+fn three_quarters() -> Matrix {
+    Matrix([[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]])
+}
+
+/// Returns the shapes that a nine slice image must produce if its corners have a half-size of
+/// `cw` by `ch`.
+fn expected(cw: f32, ch: f32) -> Vec<eui::Shape> {
+    vec![
+        image("background", Matrix::scale_wh(1.0 - cw, 1.0 - ch)),
+        image("border", Matrix::translate(0.0, 1.0 - ch) * Matrix::scale_wh(1.0 - cw, ch)),
+        image("border", Matrix::translate(-1.0 + cw, 0.0) * quarter() * Matrix::scale_wh(1.0 - ch, cw)),
+        image("border", Matrix::translate(0.0, -1.0 + ch) * half() * Matrix::scale_wh(1.0 - cw, ch)),
+        image("border", Matrix::translate(1.0 - cw, 0.0) * three_quarters() * Matrix::scale_wh(1.0 - ch, cw)),
+        image("corner", Matrix::translate(-1.0 + cw, 1.0 - ch) * Matrix::scale_wh(cw, ch)),
+        image("corner", Matrix::translate(1.0 - cw, 1.0 - ch) * Matrix::scale_wh(-cw, ch)),
+        image("corner", Matrix::translate(-1.0 + cw, -1.0 + ch) * Matrix::scale_wh(cw, -ch)),
+        image("corner", Matrix::translate(1.0 - cw, -1.0 + ch) * Matrix::scale_wh(-cw, -ch)),
+    ]
+}
+
+#[test]
+fn square() {
+    let ui = eui::Ui::new(NineSliceImage::new("corner", "border", "background", 0.25, 0.125), 1.0);
+    assert_eq!(ui.draw(), expected(0.25, 0.125));
+}
+
+#[test]
+fn tall() {
+    // the corners keep the same size relative to the height of the widget
+    let ui = eui::Ui::new(NineSliceImage::new("corner", "border", "background", 0.25, 0.125), 2.0);
+    assert_eq!(ui.draw(), expected(0.5, 0.125));
+}
+
+#[test]
+fn wide() {
+    // the corners keep the same size relative to the width of the widget
+    let ui = eui::Ui::new(NineSliceImage::new("corner", "border", "background", 0.25, 0.125), 0.5);
+    assert_eq!(ui.draw(), expected(0.25, 0.25));
+}
+
+#[test]
+fn corners_larger_than_widget() {
+    let ui = eui::Ui::new(NineSliceImage::new("corner", "border", "background", 0.75, 0.75), 4.0);
+    let shapes = ui.draw();
+    assert_eq!(shapes[5], image("corner", Matrix::translate(0.0, 0.25) * Matrix::scale_wh(1.0, 0.75)));
+}
+
+#[test]
+fn single_texture() {
+    let ui = eui::Ui::new(NineSliceImage::from_texture("frame", [0.25, 0.25], 0.25, 0.25), 2.0);
+    let shapes = ui.draw();
+    assert_eq!(shapes.len(), 9);
+
+    // background
+    assert_eq!(shapes[0], eui::Shape::Image {
+        name: "frame".to_owned(),
+        matrix: Matrix::scale_wh(0.5, 0.75),
+        uv: eui::UvRect { top: 0.75, right: 0.75, bottom: 0.25, left: 0.25 },
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+
+    // top-left corner
+    assert_eq!(shapes[5], eui::Shape::Image {
+        name: "frame".to_owned(),
+        matrix: Matrix::translate(-0.5, 0.75) * Matrix::scale_wh(0.5, 0.25),
+        uv: eui::UvRect { top: 1.0, right: 0.25, bottom: 0.75, left: 0.0 },
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+
+    // bottom-right corner
+    assert_eq!(shapes[8], eui::Shape::Image {
+        name: "frame".to_owned(),
+        matrix: Matrix::translate(0.5, -0.75) * Matrix::scale_wh(0.5, 0.25),
+        uv: eui::UvRect { top: 0.25, right: 1.0, bottom: 0.0, left: 0.75 },
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+}
@@ -0,0 +1,127 @@
+extern crate eui;
+
+#[test]
+fn rounded_rectangle() {
+    let shape = eui::Shape::RoundedRectangle {
+        matrix: eui::Matrix::translate(1.0, 0.0) * eui::Matrix::scale(0.5),
+        color: [1.0, 1.0, 1.0, 1.0],
+        radius: 0.5,
+    };
+
+    assert_eq!(shape.get_bounding_box(), (0.5, 1.5, -0.5, 0.5));
+    assert!(shape.hit_test(&[1.0, 0.0]));
+    assert!(shape.hit_test(&[1.45, 0.0]));
+    assert!(shape.hit_test(&[1.3, 0.3]));
+    // inside of the bounding box, but outside of the rounded corner
+    assert!(!shape.hit_test(&[1.45, 0.45]));
+    assert!(!shape.hit_test(&[0.0, 0.0]));
+}
+
+#[test]
+fn ellipse() {
+    let shape = eui::Shape::Ellipse {
+        matrix: eui::Matrix::scale_wh(1.0, 0.5),
+        color: [1.0, 1.0, 1.0, 1.0],
+    };
+
+    assert_eq!(shape.get_bounding_box(), (0.5, 1.0, -0.5, -1.0));
+    assert!(shape.hit_test(&[0.0, 0.0]));
+    assert!(shape.hit_test(&[0.9, 0.0]));
+    assert!(shape.hit_test(&[0.0, 0.45]));
+    assert!(!shape.hit_test(&[0.0, 0.55]));
+    assert!(!shape.hit_test(&[0.8, 0.4]));
+}
+
+#[test]
+fn line() {
+    let shape = eui::Shape::Line {
+        matrix: eui::Matrix::identity(),
+        color: [1.0, 1.0, 1.0, 1.0],
+        points: vec![[-0.5, 0.0], [0.5, 0.0], [0.5, 0.5]],
+        thickness: 0.2,
+    };
+
+    assert_eq!(shape.get_bounding_box(), (0.6, 0.6, -0.1, -0.6));
+    assert!(shape.hit_test(&[0.0, 0.05]));
+    assert!(shape.hit_test(&[-0.55, 0.0]));
+    assert!(shape.hit_test(&[0.55, 0.3]));
+    assert!(!shape.hit_test(&[0.0, 0.15]));
+    assert!(!shape.hit_test(&[0.3, 0.3]));
+}
+
+#[test]
+fn polygon() {
+    // an "L" shape
+    let shape = eui::Shape::Polygon {
+        matrix: eui::Matrix::scale(0.5),
+        color: [1.0, 1.0, 1.0, 1.0],
+        points: vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 0.0], [0.0, 0.0], [0.0, 1.0], [-1.0, 1.0]],
+    };
+
+    assert_eq!(shape.get_bounding_box(), (0.5, 0.5, -0.5, -0.5));
+    assert!(shape.hit_test(&[-0.25, 0.25]));
+    assert!(shape.hit_test(&[0.25, -0.25]));
+    assert!(!shape.hit_test(&[0.25, 0.25]));
+    assert!(!shape.hit_test(&[0.75, 0.0]));
+}
+
+#[test]
+fn empty_points() {
+    let line = eui::Shape::Line {
+        matrix: eui::Matrix::translate(0.5, -0.25),
+        color: [1.0, 1.0, 1.0, 1.0],
+        points: Vec::new(),
+        thickness: 0.2,
+    };
+
+    let polygon = eui::Shape::Polygon {
+        matrix: eui::Matrix::translate(0.5, -0.25),
+        color: [1.0, 1.0, 1.0, 1.0],
+        points: Vec::new(),
+    };
+
+    // the bounding box is reduced to the origin of the shape
+    assert_eq!(line.get_bounding_box(), (-0.25, 0.5, -0.25, 0.5));
+    assert_eq!(polygon.get_bounding_box(), (-0.25, 0.5, -0.25, 0.5));
+    assert!(!line.hit_test(&[0.5, -0.25]));
+    assert!(!polygon.hit_test(&[0.5, -0.25]));
+}
+
+#[test]
+fn uv_sub_region() {
+    let left_half = eui::UvRect { top: 1.0, right: 0.5, bottom: 0.0, left: 0.0 };
+    assert_eq!(left_half.sub_region(&eui::UvRect::full()), left_half);
+
+    let right_half = eui::UvRect { top: 1.0, right: 1.0, bottom: 0.0, left: 0.5 };
+    assert_eq!(left_half.sub_region(&right_half),
+               eui::UvRect { top: 1.0, right: 0.5, bottom: 0.0, left: 0.25 });
+}
+
+#[test]
+fn atlas_resolve() {
+    let mut atlas = eui::Atlas::new();
+    atlas.add_frame("sword", "icons", eui::UvRect { top: 1.0, right: 0.5, bottom: 0.5, left: 0.0 });
+
+    let shape = eui::Shape::Image {
+        matrix: eui::Matrix::identity(),
+        name: "sword".to_owned(),
+        uv: eui::UvRect { top: 0.5, right: 1.0, bottom: 0.0, left: 0.0 },
+        tint: [1.0, 1.0, 1.0, 1.0],
+    };
+
+    assert_eq!(atlas.resolve(shape), eui::Shape::Image {
+        matrix: eui::Matrix::identity(),
+        name: "icons".to_owned(),
+        uv: eui::UvRect { top: 0.75, right: 0.5, bottom: 0.5, left: 0.0 },
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+
+    // images that aren't in the atlas are unchanged
+    let shape = eui::Shape::Image {
+        matrix: eui::Matrix::identity(),
+        name: "shield".to_owned(),
+        uv: eui::UvRect::full(),
+        tint: [1.0, 1.0, 1.0, 1.0],
+    };
+    assert_eq!(atlas.resolve(shape.clone()), shape);
+}
@@ -0,0 +1,331 @@
+extern crate eui;
+
+use eui::predefined::Label;
+
+/// Font where the `i` is half as wide as the other characters.
+struct NarrowI;
+
+impl eui::FontMetrics for NarrowI {
+    fn line_height(&self, _: &eui::TextStyle) -> f32 {
+        2.0
+    }
+
+    fn advance(&self, c: char, _: &eui::TextStyle) -> f32 {
+        if c == 'i' { 0.5 } else { 1.0 }
+    }
+}
+
+fn text_matrix(shapes: &[eui::Shape]) -> eui::Matrix {
+    match shapes {
+        &[eui::Shape::Text { matrix, .. }] => matrix,
+        _ => panic!("{:?}", shapes),
+    }
+}
+
+#[test]
+fn default_metrics() {
+    // each character is half as wide as the line is high
+    let ui = eui::Ui::new(Label::new("abcd"), 0.25);
+    assert_eq!(text_matrix(&ui.draw()), eui::Matrix::scale_wh(0.5, 1.0));
+}
+
+#[test]
+fn custom_metrics() {
+    let ui = eui::Ui::new(Label::new("iiii"), 1.0);
+    ui.set_font_metrics(NarrowI);
+    assert_eq!(text_matrix(&ui.draw()), eui::Matrix::scale_wh(1.0, 1.0));
+
+    let ui = eui::Ui::new(Label::new("aa"), 1.0);
+    ui.set_font_metrics(NarrowI);
+    assert_eq!(text_matrix(&ui.draw()), eui::Matrix::scale_wh(1.0, 1.0));
+
+    let ui = eui::Ui::new(Label::new("aaaa"), 1.0);
+    ui.set_font_metrics(NarrowI);
+    assert_eq!(text_matrix(&ui.draw()), eui::Matrix::scale_wh(1.0, 0.5));
+}
+
+#[test]
+fn empty_label() {
+    let ui = eui::Ui::new(Label::new(""), 1.0);
+    assert!(ui.draw().is_empty());
+}
+
+#[test]
+fn wrap_words() {
+    let metrics = eui::MonospaceFontMetrics { advance: 1.0 };
+    let lines = eui::text::wrap_text("hello big  world\nfoo", Some(9.0), &Default::default(), &metrics);
+    let texts: Vec<_> = lines.iter().map(|l| (&l.text[..], l.start, l.width)).collect();
+    assert_eq!(texts, vec![("hello big", 0, 9.0), ("world", 11, 5.0), ("foo", 17, 3.0)]);
+}
+
+#[test]
+fn wrap_long_word() {
+    let metrics = eui::MonospaceFontMetrics { advance: 1.0 };
+    let lines = eui::text::wrap_text("a abcdefgh", Some(3.0), &Default::default(), &metrics);
+    let texts: Vec<_> = lines.iter().map(|l| (&l.text[..], l.start)).collect();
+    assert_eq!(texts, vec![("a", 0), ("abc", 2), ("def", 5), ("gh", 8)]);
+}
+
+#[test]
+fn wrap_empty_lines() {
+    let metrics = eui::MonospaceFontMetrics::default();
+    let lines = eui::text::wrap_text("a\n\nb", None, &Default::default(), &metrics);
+    let texts: Vec<_> = lines.iter().map(|l| &l.text[..]).collect();
+    assert_eq!(texts, vec!["a", "", "b"]);
+}
+
+#[test]
+fn layout_aligned_lines() {
+    let metrics = eui::MonospaceFontMetrics { advance: 1.0 };
+    let lines = eui::text::wrap_text("abcd\nab", None, &Default::default(), &metrics);
+    let alignment = eui::Alignment {
+        horizontal: eui::HorizontalAlignment::Left,
+        vertical: eui::VerticalAlignment::Top,
+    };
+
+    let shapes = eui::text::layout_lines(&lines, 0.5, 1.0, alignment, &Default::default(),
+                                             &metrics);
+    assert_eq!(shapes, vec![
+        eui::Shape::Text { matrix: eui::Matrix::translate(0.0, 0.75) * eui::Matrix::scale_wh(1.0, 0.25),
+                           text: "abcd".to_owned(), style: Default::default(), tint: [1.0, 1.0, 1.0, 1.0] },
+        eui::Shape::Text { matrix: eui::Matrix::translate(-0.5, 0.25) * eui::Matrix::scale_wh(0.5, 0.25),
+                           text: "ab".to_owned(), style: Default::default(), tint: [1.0, 1.0, 1.0, 1.0] },
+    ]);
+}
+
+#[test]
+fn wrapped_label() {
+    let mut label = Label::new("aaaa aaaa aaaa");
+    label.set_line_height(Some(0.25));
+
+    // with a line height of half the width and characters half as wide as high, four
+    // characters fit on a line
+    let ui = eui::Ui::new(label, 2.0);
+    let shapes = ui.draw();
+    assert_eq!(shapes.len(), 3);
+    assert_eq!(shapes[0], eui::Shape::Text {
+        matrix: eui::Matrix::translate(0.0, 0.5) * eui::Matrix::scale_wh(1.0, 0.25),
+        text: "aaaa".to_owned(),
+        style: eui::TextStyle { size: Some(0.25), .. Default::default() },
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+}
+
+#[test]
+fn styled_label() {
+    /// Font where bold characters are twice as wide.
+    struct BoldIsWide;
+
+    impl eui::FontMetrics for BoldIsWide {
+        fn line_height(&self, _: &eui::TextStyle) -> f32 {
+            1.0
+        }
+
+        fn advance(&self, _: char, style: &eui::TextStyle) -> f32 {
+            if style.bold { 1.0 } else { 0.5 }
+        }
+    }
+
+    let style = eui::TextStyle {
+        font: "title".to_owned(),
+        color: [1.0, 0.0, 0.0, 1.0],
+        bold: true,
+        outline: Some(eui::text::Outline { color: [0.0, 0.0, 0.0, 1.0], thickness: 0.1 }),
+        .. Default::default()
+    };
+
+    let mut label = Label::new("ab");
+    label.set_style(style.clone());
+
+    let ui = eui::Ui::new(label, 0.25);
+    ui.set_font_metrics(BoldIsWide);
+    assert_eq!(ui.draw(), vec![eui::Shape::Text {
+        matrix: eui::Matrix::scale_wh(0.5, 1.0),
+        text: "ab".to_owned(),
+        style: style,
+        tint: [1.0, 1.0, 1.0, 1.0],
+    }]);
+}
+
+#[test]
+fn rich_text() {
+    let red = eui::TextStyle { color: [1.0, 0.0, 0.0, 1.0], .. Default::default() };
+    let spans = vec![
+        eui::text::Span::Text { text: "get ".to_owned(), style: Default::default() },
+        eui::text::Span::Text { text: "sword".to_owned(), style: red.clone() },
+        eui::text::Span::Image { name: "icon".to_owned(), width: 1.0 },
+        eui::text::Span::Text { text: " now".to_owned(), style: Default::default() },
+    ];
+
+    // "get sword" and the icon are on the first line, "now" on the second line
+    let metrics = eui::MonospaceFontMetrics { advance: 1.0 };
+    let lines = eui::text::wrap_spans(&spans, Some(10.0), &metrics);
+    let pieces: Vec<Vec<_>> = lines.iter().map(|l| {
+        l.pieces.iter().map(|p| (p.span, &p.text[..], p.width)).collect()
+    }).collect();
+    assert_eq!(pieces, vec![
+        vec![(0, "get ", 4.0), (1, "sword", 5.0), (2, "", 1.0)],
+        vec![(3, "now", 3.0)],
+    ]);
+
+    let mut widget = eui::predefined::RichText::new(spans);
+    widget.set_line_height(Some(0.3125));
+
+    // the same lines are produced in the widget, which is as wide as 6.4 line heights
+    let ui = eui::Ui::new(widget, 0.5);
+    let shapes = ui.draw();
+    assert_eq!(shapes.len(), 4);
+    assert_eq!(shapes[1], eui::Shape::Text {
+        matrix: eui::Matrix::translate(0.15625, 0.3125) * eui::Matrix::scale_wh(0.390625, 0.3125),
+        text: "sword".to_owned(),
+        style: red,
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+    assert_eq!(shapes[2], eui::Shape::Image {
+        matrix: eui::Matrix::translate(0.703125, 0.3125) * eui::Matrix::scale_wh(0.15625, 0.3125),
+        name: "icon".to_owned(),
+        uv: eui::UvRect::full(),
+        tint: [1.0, 1.0, 1.0, 1.0],
+    });
+}
+
+/// Font metrics where the font named "small" is half as high and half as wide as the others.
+struct SmallFont;
+
+impl eui::FontMetrics for SmallFont {
+    fn line_height(&self, style: &eui::TextStyle) -> f32 {
+        if style.font == "small" { 0.5 } else { 1.0 }
+    }
+
+    fn advance(&self, _: char, style: &eui::TextStyle) -> f32 {
+        if style.font == "small" { 0.25 } else { 0.5 }
+    }
+}
+
+#[test]
+fn rich_text_mixed_sizes() {
+    let small = eui::TextStyle { font: "small".to_owned(), .. Default::default() };
+    let spans = vec![
+        eui::text::Span::Text { text: "ab".to_owned(), style: Default::default() },
+        eui::text::Span::Text { text: "cd".to_owned(), style: small },
+        eui::text::Span::Image { name: "icon".to_owned(), width: 1.0 },
+    ];
+
+    let alignment = eui::Alignment {
+        horizontal: eui::HorizontalAlignment::Left,
+        vertical: eui::VerticalAlignment::Top,
+    };
+    let lines = eui::text::wrap_spans(&spans, None, &SmallFont);
+    let shapes = eui::text::layout_span_lines(&spans, &lines, 1.0, 0.5, alignment, &SmallFont);
+    let matrices: Vec<_> = shapes.iter().map(|s| *s.matrix()).collect();
+
+    // the pieces are aligned on the bottom of the line, and the small text keeps the
+    // proportions of its font: two characters are as wide as a line is high
+    assert_eq!(matrices, vec![
+        eui::Matrix::translate(-0.75, 0.5) * eui::Matrix::scale_wh(0.25, 0.5),
+        eui::Matrix::translate(-0.375, 0.25) * eui::Matrix::scale_wh(0.125, 0.25),
+        eui::Matrix::translate(0.0, 0.5) * eui::Matrix::scale_wh(0.25, 0.5),
+    ]);
+
+    let (top, right, bottom, left) = shapes[1].get_bounding_box();
+    assert_eq!((right - left) / ((top - bottom) * 0.5), 1.0);
+}
+
+fn texts(shapes: &[eui::Shape]) -> Vec<&str> {
+    shapes.iter().map(|s| match s {
+        &eui::Shape::Text { ref text, .. } => &text[..],
+        _ => panic!("{:?}", s),
+    }).collect()
+}
+
+#[test]
+fn overflow_ellipsis() {
+    // a line is half of the label, and eight characters fit on a line
+    let mut label = Label::new("abcdefghij");
+    label.set_line_height(Some(0.5));
+    label.set_wrap(false);
+    label.set_overflow(eui::text::Overflow::Ellipsis);
+    let ui = eui::Ui::new(label, 0.5);
+    assert_eq!(texts(&ui.draw()), vec!["abcdefg\u{2026}"]);
+
+    // only two lines fit
+    let mut label = Label::new("aaaa bbbb cccc");
+    label.set_line_height(Some(0.5));
+    label.set_overflow(eui::text::Overflow::Ellipsis);
+    let ui = eui::Ui::new(label, 0.5);
+    assert_eq!(texts(&ui.draw()), vec!["aaaa", "bbbb\u{2026}"]);
+}
+
+#[test]
+fn overflow_shrink_to_fit() {
+    let mut label = Label::new("abcdefghij");
+    label.set_line_height(Some(0.5));
+    label.set_wrap(false);
+    label.set_overflow(eui::text::Overflow::ShrinkToFit { min_size: 0.25 });
+    let ui = eui::Ui::new(label, 0.5);
+    let shapes = ui.draw();
+    assert_eq!(texts(&shapes), vec!["abcdefghij"]);
+
+    // the text is as wide as the label
+    let (_, right, _, left) = shapes[0].get_bounding_box();
+    assert!((right - left - 2.0).abs() < 1e-3);
+
+    // the minimum size is too large for the text to fit
+    let mut label = Label::new("abcdefghij");
+    label.set_line_height(Some(0.5));
+    label.set_wrap(false);
+    label.set_overflow(eui::text::Overflow::ShrinkToFit { min_size: 0.45 });
+    let ui = eui::Ui::new(label, 0.5);
+    assert_eq!(texts(&ui.draw()), vec!["abcdefg\u{2026}"]);
+}
+
+#[test]
+fn overflow_clip() {
+    let mut label = Label::new("abcdefghij");
+    label.set_line_height(Some(0.5));
+    label.set_wrap(false);
+    label.set_overflow(eui::text::Overflow::Clip);
+    let ui = eui::Ui::new(label, 0.5);
+    let list = ui.draw_list();
+    assert_eq!(texts(&list), vec!["abcdefghij"]);
+    assert_eq!(list.clip_rects(),
+               &[Some(eui::ClipRect { top: 1.0, right: 1.0, bottom: -1.0, left: -1.0 })]);
+}
+
+#[test]
+fn overflow_default_style() {
+    // the label is twice as wide as high, so four characters fit when the text is as high
+    // as the label
+    let mut label = Label::new("abcdefghij");
+    label.set_overflow(eui::text::Overflow::Ellipsis);
+    let ui = eui::Ui::new(label, 0.5);
+    let shapes = ui.draw();
+    assert_eq!(texts(&shapes), vec!["abc\u{2026}"]);
+    let (top, _, bottom, _) = shapes[0].get_bounding_box();
+    assert_eq!(top - bottom, 2.0);
+
+    // the text fits above the minimum size
+    let mut label = Label::new("abcdefghij");
+    label.set_overflow(eui::text::Overflow::ShrinkToFit { min_size: 0.25 });
+    let ui = eui::Ui::new(label, 0.5);
+    let shapes = ui.draw();
+    assert_eq!(texts(&shapes), vec!["abcdefghij"]);
+    let (_, right, _, left) = shapes[0].get_bounding_box();
+    assert!((right - left - 2.0).abs() < 1e-3);
+
+    // the text is truncated below the minimum size
+    let mut label = Label::new("abcdefghij");
+    label.set_overflow(eui::text::Overflow::ShrinkToFit { min_size: 0.45 });
+    let ui = eui::Ui::new(label, 0.5);
+    assert_eq!(texts(&ui.draw()), vec!["abcdefg\u{2026}"]);
+
+    let mut label = Label::new("abcdefghij");
+    label.set_overflow(eui::text::Overflow::Clip);
+    let ui = eui::Ui::new(label, 0.5);
+    let list = ui.draw_list();
+    assert_eq!(texts(&list), vec!["abcdefghij"]);
+    let (_, right, _, left) = list[0].get_bounding_box();
+    assert_eq!(right - left, 5.0);
+    assert_eq!(list.clip_rects(),
+               &[Some(eui::ClipRect { top: 1.0, right: 1.0, bottom: -1.0, left: -1.0 })]);
+}
@@ -0,0 +1,224 @@
+extern crate eui;
+
+use std::any::Any;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use eui::Clipboard;
+
+/// Widget containing a text input and recording the texts that are submitted.
+struct Form {
+    input: Arc<eui::predefined::TextInput>,
+    submitted: Mutex<Vec<String>>,
+}
+
+impl Form {
+    fn new(input: eui::predefined::TextInput) -> Form {
+        Form { input: Arc::new(input), submitted: Mutex::new(Vec::new()) }
+    }
+}
+
+impl eui::Widget for Form {
+    fn build_layout(&self, _: f32, _: eui::Alignment) -> eui::Layout {
+        eui::Layout::AbsolutePositionned(vec![(eui::Matrix::identity(), self.input.clone())])
+    }
+
+    fn handle_event(&self, event: &Any, _: Option<usize>) -> eui::EventOutcome {
+        if let Some(ev) = event.downcast_ref::<eui::predefined::TextSubmittedEvent>() {
+            self.submitted.lock().unwrap().push(ev.text.clone());
+        }
+
+        Default::default()
+    }
+}
+
+fn shift() -> eui::Modifiers {
+    eui::Modifiers { shift: true, .. Default::default() }
+}
+
+#[test]
+fn editing() {
+    let ui = eui::Ui::new(Form::new(eui::predefined::TextInput::new("")), 1.0);
+    let input = ui.widget().input.clone();
+
+    ui.focus_next();
+    for c in "hello".chars() {
+        ui.character(c);
+    }
+    assert_eq!(input.text(), "hello");
+    assert_eq!(input.caret(), 5);
+
+    ui.key_down(eui::Key::Backspace, Default::default());
+    ui.key_down(eui::Key::Left, Default::default());
+    ui.character('é');
+    assert_eq!(input.text(), "helél");
+    assert_eq!(input.caret(), 5);
+
+    ui.key_down(eui::Key::Home, Default::default());
+    ui.key_down(eui::Key::Delete, Default::default());
+    assert_eq!(input.text(), "elél");
+
+    // control characters are ignored
+    ui.character('\u{8}');
+    assert_eq!(input.text(), "elél");
+}
+
+#[test]
+fn selection() {
+    let ui = eui::Ui::new(Form::new(eui::predefined::TextInput::new("hello world")), 1.0);
+    let input = ui.widget().input.clone();
+    ui.focus_next();
+
+    ui.key_down(eui::Key::Left, shift());
+    ui.key_down(eui::Key::Left, shift());
+    assert_eq!(input.selection(), (9, 11));
+
+    ui.character('!');
+    assert_eq!(input.text(), "hello wor!");
+    assert_eq!(input.selection(), (10, 10));
+
+    ui.key_down(eui::Key::Home, shift());
+    assert_eq!(input.selection(), (0, 10));
+    ui.key_down(eui::Key::Right, Default::default());
+    assert_eq!(input.selection(), (10, 10));
+
+    ui.key_down(eui::Key::Char('a'), eui::Modifiers { ctrl: true, .. Default::default() });
+    ui.key_down(eui::Key::Backspace, Default::default());
+    assert_eq!(input.text(), "");
+}
+
+#[test]
+fn click_places_caret() {
+    let ui = eui::Ui::new(Form::new(eui::predefined::TextInput::new("abcd")), 0.25);
+    let input = ui.widget().input.clone();
+    ui.draw();
+
+    // with the default metrics, each character is a quarter of the widget's width wide
+    ui.set_cursor(Some([-0.7, 0.0]), true);
+    ui.set_cursor(Some([-0.7, 0.0]), false);
+    assert_eq!(input.caret(), 1);
+
+    // the text input has the focus
+    ui.character('x');
+    assert_eq!(input.text(), "axbcd");
+    ui.draw();
+
+    // dragging selects text
+    ui.set_cursor(Some([0.3, 0.0]), true);
+    ui.set_cursor_position(Some([-0.95, 0.0]));
+    ui.set_cursor(Some([-0.95, 0.0]), false);
+    assert_eq!(input.selection(), (0, 5));
+    assert_eq!(input.caret(), 0);
+}
+
+#[test]
+fn submitted() {
+    let ui = eui::Ui::new(Form::new(eui::predefined::TextInput::new("name")), 1.0);
+    ui.focus_next();
+
+    ui.key_down(eui::Key::Enter, Default::default());
+    assert_eq!(*ui.widget().submitted.lock().unwrap(), &["name"]);
+    assert_eq!(ui.widget().input.text(), "name");
+}
+
+#[test]
+fn horizontal_scrolling() {
+    let mut input = eui::predefined::TextInput::new("");
+    input.set_style(eui::TextStyle { size: Some(0.25), .. Default::default() });
+    input.set_blink_interval(0);
+    let ui = eui::Ui::new(Form::new(input), 1.0);
+    ui.focus_next();
+
+    for _ in 0 .. 20 {
+        ui.character('a');
+    }
+
+    let shapes = ui.draw();
+    let text = shapes.iter().find(|s| matches!(s, &&eui::Shape::Text { .. })).unwrap();
+    let caret = shapes.last().unwrap();
+
+    // the end of the text and the caret are visible, the beginning is hidden on the left
+    let (_, text_right, _, text_left) = text.get_bounding_box();
+    let (_, caret_right, _, _) = caret.get_bounding_box();
+    assert!((caret_right - 1.0).abs() < 1e-4);
+    assert!(text_right < 1.0);
+    assert!(text_left < -1.0);
+
+    // going back to the start scrolls the other way
+    ui.key_down(eui::Key::Home, Default::default());
+    let shapes = ui.draw();
+    let text = shapes.iter().find(|s| matches!(s, &&eui::Shape::Text { .. })).unwrap();
+    let (_, text_right, _, text_left) = text.get_bounding_box();
+    assert!((text_left + 1.0).abs() < 1e-4);
+    assert!(text_right > 1.0);
+}
+
+#[test]
+fn ime_composition() {
+    let ui = eui::Ui::new(Form::new(eui::predefined::TextInput::new("ab")), 0.25);
+    let input = ui.widget().input.clone();
+    assert_eq!(ui.caret_rect(), None);
+
+    ui.focus_next();
+    ui.draw();
+
+    // with the default metrics, each character is a quarter of the widget's width wide
+    let rect = ui.caret_rect().unwrap();
+    assert!((rect.left + 0.5).abs() < 1e-4);
+    assert!((rect.top - 1.0).abs() < 1e-4);
+    assert!((rect.bottom + 1.0).abs() < 1e-4);
+
+    // the text being composed is displayed but isn't part of the content
+    ui.ime_preedit("にほ", Some(3));
+    assert_eq!(input.text(), "ab");
+    let shapes = ui.draw();
+    assert!(shapes.iter().any(|s| match s {
+        &eui::Shape::Text { ref text, .. } => text == "abにほ",
+        _ => false,
+    }));
+    let rect = ui.caret_rect().unwrap();
+    assert!((rect.left + 0.25).abs() < 1e-4);
+
+    ui.ime_commit("日本");
+    assert_eq!(input.text(), "ab日本");
+    assert_eq!(input.caret(), 8);
+
+    // cancelling a composition leaves the content unchanged
+    ui.ime_preedit("ご", None);
+    ui.ime_preedit("", None);
+    assert_eq!(input.text(), "ab日本");
+}
+
+#[test]
+fn clipboard() {
+    let ui = eui::Ui::new(Form::new(eui::predefined::TextInput::new("hello world")), 1.0);
+    let input = ui.widget().input.clone();
+    let clipboard = Arc::new(eui::MemoryClipboard::new());
+    ui.set_clipboard(clipboard.clone());
+    ui.focus_next();
+
+    let ctrl = eui::Modifiers { ctrl: true, .. Default::default() };
+
+    // nothing is copied if the selection is empty
+    ui.key_down(eui::Key::Char('c'), ctrl);
+    assert_eq!(clipboard.get_text(), None);
+
+    input.set_selection(0, 5);
+    ui.key_down(eui::Key::Char('c'), ctrl);
+    assert_eq!(clipboard.get_text(), Some("hello".to_owned()));
+    assert_eq!(input.text(), "hello world");
+
+    input.set_selection(5, 11);
+    ui.key_down(eui::Key::Char('x'), ctrl);
+    assert_eq!(clipboard.get_text(), Some(" world".to_owned()));
+    assert_eq!(input.text(), "hello");
+
+    ui.key_down(eui::Key::Home, Default::default());
+    ui.key_down(eui::Key::Char('v'), ctrl);
+    assert_eq!(input.text(), " worldhello");
+
+    // line breaks are removed from the pasted text
+    clipboard.set_text("a\nb".to_owned());
+    ui.paste();
+    assert_eq!(input.text(), " worldabhello");
+}